@@ -0,0 +1,69 @@
+use std::io::{self, Cursor};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use mapper::exporters::Csv;
+use mapper::formats::SectionGroups;
+use mapper::generator::{generate, Options, PathStyle};
+use mapper::{Exporter, MapFile};
+
+/// Generated maps of these sizes in bytes.
+const SIZES: [u64; 3] = [64 << 10, 1 << 20, 16 << 20];
+
+fn maps() -> impl Iterator<Item = (String, String)> {
+    SIZES.into_iter().flat_map(|size| {
+        PathStyle::ALL.into_iter().map(move |paths| {
+            let options = Options {
+                paths,
+                archives: paths == PathStyle::Windows,
+                ..Default::default()
+            }
+            .with_size(size);
+            let size = match size >= 1 << 20 {
+                true => format!("{}M", size >> 20),
+                false => format!("{}K", size >> 10),
+            };
+            (format!("{size}/{}", paths.name()), generate(&options))
+        })
+    })
+}
+
+fn parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+    for (name, map) in maps() {
+        group.throughput(Throughput::Bytes(map.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &map, |b, map| {
+            b.iter(|| MapFile::parse(map).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn stream(c: &mut Criterion) {
+    let mut group = c.benchmark_group("stream");
+    for (name, map) in maps() {
+        group.throughput(Throughput::Bytes(map.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &map, |b, map| {
+            b.iter(|| {
+                SectionGroups::new(Cursor::new(map.as_bytes()))
+                    .map(Result::unwrap)
+                    .count()
+            })
+        });
+    }
+    group.finish();
+}
+
+fn csv(c: &mut Criterion) {
+    let mut group = c.benchmark_group("csv");
+    for (name, map) in maps() {
+        let parsed = MapFile::parse(&map).unwrap();
+        group.throughput(Throughput::Bytes(map.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &parsed, |b, map| {
+            b.iter(|| Csv.write(map, &mut io::sink()).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, parse, stream, csv);
+criterion_main!(benches);
@@ -0,0 +1,43 @@
+use std::fmt;
+
+use nom::IResult;
+
+/// A map file that could not be parsed in the format it was parsed as.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// Name of the format, e.g. `gnu`.
+    pub format: &'static str,
+    /// Line the parser could not get past, counting from 1.
+    pub line: usize,
+}
+
+impl ParseError {
+    pub(crate) fn new<T>(format: &'static str, input: &str, result: &IResult<&str, T>) -> Self {
+        let rest = match result {
+            Err(nom::Err::Error(e) | nom::Err::Failure(e)) => e.input,
+            _ => &input[input.len()..],
+        };
+        Self {
+            format,
+            line: line_number(input, rest),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "input is not a valid {} map file (line {})",
+            self.format, self.line
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Line of `input` that `rest`, a suffix of `input`, starts on.
+pub(crate) fn line_number(input: &str, rest: &str) -> usize {
+    let offset = input.len().saturating_sub(rest.len());
+    input[..offset].matches('\n').count() + 1
+}
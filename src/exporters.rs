@@ -0,0 +1,103 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::types::MapFile;
+
+mod bloaty;
+mod documents;
+mod folded;
+mod html;
+mod puncover;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+mod symbols;
+#[cfg(feature = "xlsx")]
+mod xlsx;
+
+pub use bloaty::{Bloaty, DataSource};
+pub use documents::{Json, Toml, Yaml};
+pub use folded::Folded;
+pub use html::Html;
+pub use puncover::Puncover;
+#[cfg(feature = "sqlite")]
+pub use sqlite::Sqlite;
+pub use symbols::{Csv, Markdown, Text};
+#[cfg(feature = "xlsx")]
+pub use xlsx::Xlsx;
+
+/// A way of writing a parsed map file out.
+pub trait Exporter {
+    /// Short identifier of the format, e.g. `csv`, as selected with `--format`.
+    fn name(&self) -> &'static str;
+
+    /// Extension of the files the format is written to, without the dot.
+    fn extension(&self) -> &'static str;
+
+    fn write(&self, map: &MapFile, writer: &mut dyn Write) -> anyhow::Result<()>;
+
+    /// Writes the map file to `path`, replacing it if it exists.
+    fn export(&self, map: &MapFile, path: &Path) -> anyhow::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(map, &mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// The set of formats a map file can be exported to.
+///
+/// `Exporters::default()` contains the formats built into mapper; additional formats are added
+/// with [`Exporters::register`].
+pub struct Exporters {
+    exporters: Vec<Box<dyn Exporter>>,
+}
+
+impl Exporters {
+    pub fn new() -> Self {
+        Self {
+            exporters: Vec::new(),
+        }
+    }
+
+    /// Adds `exporter`, replacing any registered exporter with the same name.
+    pub fn register(&mut self, exporter: impl Exporter + 'static) -> &mut Self {
+        self.exporters.retain(|e| e.name() != exporter.name());
+        self.exporters.push(Box::new(exporter));
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn Exporter> {
+        self.exporters
+            .iter()
+            .find(|e| e.name() == name)
+            .map(|e| e.as_ref())
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        self.exporters.iter().map(|e| e.name()).collect()
+    }
+}
+
+impl Default for Exporters {
+    fn default() -> Self {
+        let mut exporters = Self::new();
+        exporters
+            .register(Csv)
+            .register(Json)
+            .register(Yaml)
+            .register(Toml);
+        #[cfg(feature = "sqlite")]
+        exporters.register(Sqlite);
+        #[cfg(feature = "xlsx")]
+        exporters.register(Xlsx);
+        exporters
+            .register(Html)
+            .register(Markdown)
+            .register(Text)
+            .register(Folded)
+            .register(Bloaty::default())
+            .register(Puncover);
+        exporters
+    }
+}
@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+use std::io::Write;
+use std::str::FromStr;
+
+use csv::Writer;
+
+use super::Exporter;
+use crate::reports::{is_non_alloc, is_zero_initialised, symbol_sizes};
+use crate::types::*;
+use crate::units::hex_value;
+
+/// A way of breaking the output down, named like bloaty's data sources (`bloaty -d`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DataSource {
+    /// Output sections.
+    Sections,
+    /// Symbols, each taking the bytes up to the next symbol.
+    Symbols,
+    /// Input files, which stand in for the compile units bloaty reads from debug information.
+    CompileUnits,
+}
+
+impl DataSource {
+    pub const ALL: [DataSource; 3] = [
+        DataSource::Sections,
+        DataSource::Symbols,
+        DataSource::CompileUnits,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DataSource::Sections => "sections",
+            DataSource::Symbols => "symbols",
+            DataSource::CompileUnits => "compileunits",
+        }
+    }
+}
+
+impl FromStr for DataSource {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        DataSource::ALL
+            .into_iter()
+            .find(|source| source.name() == name)
+            .ok_or_else(|| format!("unknown data source {name}"))
+    }
+}
+
+/// Bytes attributed to one section, input file and symbol.
+struct Entry<'a> {
+    section: &'a str,
+    compile_unit: &'a str,
+    symbol: &'a str,
+    size: u64,
+    vm: bool,
+    file: bool,
+}
+
+/// Every byte of the output sections, attributed to the finest level the map file knows.
+/// Bytes no input file or symbol accounts for are attributed to `[section NAME]`, as bloaty
+/// does.
+fn entries<'a>(map: &'a MapFile, unattributed: &'a HashMap<&'a str, String>) -> Vec<Entry<'a>> {
+    let mut entries = Vec::new();
+    for group in &map.sections {
+        let name = &*group.section.name;
+        let size = hex_value(&group.section.size).unwrap_or(0);
+        // non-alloc sections take no memory, zero initialised ones nothing in the file
+        let vm = !is_non_alloc(name);
+        let file = !is_zero_initialised(name);
+        let other = &unattributed[name];
+        let entry = |compile_unit, symbol, size| Entry {
+            section: name,
+            compile_unit,
+            symbol,
+            size,
+            vm,
+            file,
+        };
+
+        let mut attributed = 0;
+        for fsg in &group.file_section_groups {
+            let compile_unit = &*fsg.file_section.file;
+            let file_size = hex_value(&fsg.file_section.size).unwrap_or(0);
+            let mut covered = 0;
+            for symbol in symbol_sizes(fsg) {
+                covered += symbol.size;
+                entries.push(entry(compile_unit, symbol.name, symbol.size));
+            }
+            if file_size > covered {
+                entries.push(entry(compile_unit, other, file_size - covered));
+            }
+            attributed += file_size;
+        }
+        if size > attributed {
+            entries.push(entry(other, other, size - attributed));
+        }
+    }
+    entries
+}
+
+/// The sizes of the output as CSV in the layout of `bloaty --csv -d <sources>`: a column per
+/// data source followed by `vmsize` and `filesize`, largest first.
+///
+/// Map files do not say which sections take space in the file, so sections named like
+/// `.bss` are taken to have no file size and debug sections no VM size.
+pub struct Bloaty {
+    pub sources: Vec<DataSource>,
+}
+
+impl Default for Bloaty {
+    fn default() -> Self {
+        Self {
+            sources: vec![DataSource::Sections],
+        }
+    }
+}
+
+impl Exporter for Bloaty {
+    fn name(&self) -> &'static str {
+        "bloaty"
+    }
+
+    fn extension(&self) -> &'static str {
+        "csv"
+    }
+
+    fn write(&self, map: &MapFile, writer: &mut dyn Write) -> anyhow::Result<()> {
+        let sources = &self.sources;
+        let unattributed: HashMap<&str, String> = map
+            .sections
+            .iter()
+            .map(|g| (&*g.section.name, format!("[section {}]", g.section.name)))
+            .collect();
+
+        let mut rows: Vec<(Vec<&str>, u64, u64)> = Vec::new();
+        let mut index: HashMap<Vec<&str>, usize> = HashMap::new();
+        for entry in entries(map, &unattributed) {
+            let key: Vec<&str> = sources
+                .iter()
+                .map(|source| match source {
+                    DataSource::Sections => entry.section,
+                    DataSource::Symbols => entry.symbol,
+                    DataSource::CompileUnits => entry.compile_unit,
+                })
+                .collect();
+            let row = *index.entry(key.clone()).or_insert_with(|| {
+                rows.push((key, 0, 0));
+                rows.len() - 1
+            });
+            if entry.vm {
+                rows[row].1 += entry.size;
+            }
+            if entry.file {
+                rows[row].2 += entry.size;
+            }
+        }
+        rows.sort_by(|a, b| (b.1, b.2).cmp(&(a.1, a.2)).then_with(|| a.0.cmp(&b.0)));
+
+        let mut writer = Writer::from_writer(writer);
+        let header = sources.iter().map(|source| source.name());
+        writer.write_record(header.chain(["vmsize", "filesize"]))?;
+        for (key, vmsize, filesize) in rows {
+            let sizes = [vmsize.to_string(), filesize.to_string()];
+            writer.write_record(key.iter().copied().chain(sizes.iter().map(String::as_str)))?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,67 @@
+use std::io::Write;
+
+use super::Exporter;
+use crate::types::{Export, MapFile};
+use crate::SCHEMA_VERSION;
+
+fn document<'a>(map: &'a MapFile<'a>) -> Export<'a> {
+    Export {
+        schema_version: SCHEMA_VERSION,
+        map,
+    }
+}
+
+/// The whole map file, metadata included, as JSON. The layout is described by
+/// [`schema`](crate::schema).
+pub struct Json;
+
+impl Exporter for Json {
+    fn name(&self) -> &'static str {
+        "json"
+    }
+
+    fn extension(&self) -> &'static str {
+        "json"
+    }
+
+    fn write(&self, map: &MapFile, writer: &mut dyn Write) -> anyhow::Result<()> {
+        serde_json::to_writer_pretty(writer, &document(map))?;
+        Ok(())
+    }
+}
+
+/// The document of [`Json`] as YAML.
+pub struct Yaml;
+
+impl Exporter for Yaml {
+    fn name(&self) -> &'static str {
+        "yaml"
+    }
+
+    fn extension(&self) -> &'static str {
+        "yaml"
+    }
+
+    fn write(&self, map: &MapFile, writer: &mut dyn Write) -> anyhow::Result<()> {
+        serde_yaml::to_writer(writer, &document(map))?;
+        Ok(())
+    }
+}
+
+/// The document of [`Json`] as TOML. TOML has no null, so fields without a value are left out.
+pub struct Toml;
+
+impl Exporter for Toml {
+    fn name(&self) -> &'static str {
+        "toml"
+    }
+
+    fn extension(&self) -> &'static str {
+        "toml"
+    }
+
+    fn write(&self, map: &MapFile, writer: &mut dyn Write) -> anyhow::Result<()> {
+        writer.write_all(toml::to_string(&document(map))?.as_bytes())?;
+        Ok(())
+    }
+}
@@ -0,0 +1,57 @@
+use std::io::Write;
+
+use super::Exporter;
+use crate::reports::{size_tree, SizeNode};
+use crate::types::*;
+use crate::units::archive_member;
+
+/// The sizes of the map file as folded stacks, one line per symbol such as
+/// `FLASH;.text;libfoo.a;bar.o;my_func 1234`, the input of flame graph tools like inferno,
+/// `flamegraph.pl` and speedscope.
+///
+/// The frames are the memory region, output section, archive (for archive members only),
+/// input file and symbol, weighted by size in bytes. Bytes not covered by a symbol end in an
+/// `(other)` frame.
+pub struct Folded;
+
+impl Exporter for Folded {
+    fn name(&self) -> &'static str {
+        "folded"
+    }
+
+    fn extension(&self) -> &'static str {
+        "folded"
+    }
+
+    fn write(&self, map: &MapFile, writer: &mut dyn Write) -> anyhow::Result<()> {
+        let tree = size_tree(map);
+        let mut stack = Vec::new();
+        for region in &tree.children {
+            write_node(writer, &mut stack, region)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes a line for each leaf under `node`, `stack` holding the frames above it.
+fn write_node<'a>(
+    writer: &mut dyn Write,
+    stack: &mut Vec<&'a str>,
+    node: &'a SizeNode<'a>,
+) -> anyhow::Result<()> {
+    let depth = stack.len();
+    // input files are the third level: region, section, file
+    match archive_member(node.name).filter(|_| depth == 2) {
+        Some((archive, member)) => stack.extend([archive, member]),
+        None => stack.push(node.name),
+    }
+    if node.children.is_empty() {
+        let frames: Vec<String> = stack.iter().map(|frame| frame.replace(';', ":")).collect();
+        writeln!(writer, "{} {}", frames.join(";"), node.size)?;
+    }
+    for child in &node.children {
+        write_node(writer, stack, child)?;
+    }
+    stack.truncate(depth);
+    Ok(())
+}
@@ -0,0 +1,52 @@
+use std::io::Write;
+
+use serde::Serialize;
+
+use super::Exporter;
+use crate::reports::{size_tree, SizeNode};
+use crate::types::*;
+
+const TEMPLATE: &str = include_str!("html/report.html");
+
+#[derive(Serialize)]
+struct Report<'a> {
+    title: &'a str,
+    tree: SizeNode<'a>,
+}
+
+/// A report of the map file as a single HTML page, with a treemap of the sizes of regions,
+/// sections, input files and symbols, and tables of sections and symbols. The page embeds its
+/// data, styles and scripts, so it can be opened without network access.
+pub struct Html;
+
+impl Exporter for Html {
+    fn name(&self) -> &'static str {
+        "html"
+    }
+
+    fn extension(&self) -> &'static str {
+        "html"
+    }
+
+    fn write(&self, map: &MapFile, writer: &mut dyn Write) -> anyhow::Result<()> {
+        let title = map.source.as_deref().unwrap_or("map file");
+        let report = Report {
+            title,
+            tree: size_tree(map),
+        };
+        // `<` is escaped so that names like `</script>` cannot end the data block
+        let data = serde_json::to_string(&report)?.replace('<', "\\u003c");
+        let content = TEMPLATE
+            .replace("{{title}}", &escape(title))
+            .replace("{{data}}", &data);
+        writer.write_all(content.as_bytes())?;
+        Ok(())
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
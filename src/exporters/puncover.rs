@@ -0,0 +1,61 @@
+use std::io::Write;
+
+use csv::Writer;
+
+use super::Exporter;
+use crate::reports::{is_non_alloc, symbol_sizes};
+use crate::types::*;
+
+/// The symbols of the map file as CSV in the style of puncover's symbol table: one row per
+/// symbol with its input file, whether it is a function or a variable and its size under
+/// `code` or `static`, largest first.
+///
+/// Symbols in input sections named like `.text` are functions and count as code; all other
+/// symbols are variables and count as static memory. Symbols take the bytes up to the next
+/// symbol, and debug sections are left out. Map files say nothing about stack usage, so unlike
+/// puncover there is no `stack` column.
+pub struct Puncover;
+
+impl Exporter for Puncover {
+    fn name(&self) -> &'static str {
+        "puncover"
+    }
+
+    fn extension(&self) -> &'static str {
+        "csv"
+    }
+
+    fn write(&self, map: &MapFile, writer: &mut dyn Write) -> anyhow::Result<()> {
+        let mut rows = Vec::new();
+        for group in &map.sections {
+            if is_non_alloc(&group.section.name) {
+                continue;
+            }
+            for fsg in &group.file_section_groups {
+                let function = is_code(&fsg.file_section.section);
+                for symbol in symbol_sizes(fsg) {
+                    rows.push((&*fsg.file_section.file, symbol.name, function, symbol.size));
+                }
+            }
+        }
+        rows.sort_by(|a, b| b.3.cmp(&a.3).then_with(|| (a.0, a.1).cmp(&(b.0, b.1))));
+
+        let mut writer = Writer::from_writer(writer);
+        writer.write_record(["file", "symbol", "type", "code", "static"])?;
+        for (file, symbol, function, size) in rows {
+            let size = size.to_string();
+            let (kind, code, stat) = match function {
+                true => ("function", size.as_str(), "0"),
+                false => ("variable", "0", size.as_str()),
+            };
+            writer.write_record([file, symbol, kind, code, stat])?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Whether an input section named `name`, e.g. `.text.main`, holds code.
+fn is_code(name: &str) -> bool {
+    name == ".text" || name.starts_with(".text.")
+}
@@ -0,0 +1,276 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::bail;
+use rusqlite::{params, Connection};
+
+use super::Exporter;
+use crate::load_address;
+use crate::reports::{is_non_alloc, located_regions, region_at};
+use crate::types::*;
+use crate::units::{archive_member, hex_value};
+
+const SCHEMA: &str = "
+CREATE TABLE memory_regions (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    origin INTEGER,
+    length INTEGER,
+    attributes TEXT NOT NULL
+);
+CREATE TABLE sections (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    address INTEGER,
+    size INTEGER,
+    lma INTEGER,
+    memory_region_id INTEGER REFERENCES memory_regions (id)
+);
+CREATE TABLE archives (
+    id INTEGER PRIMARY KEY,
+    path TEXT NOT NULL UNIQUE
+);
+CREATE TABLE files (
+    id INTEGER PRIMARY KEY,
+    path TEXT NOT NULL UNIQUE,
+    archive_id INTEGER REFERENCES archives (id),
+    member TEXT
+);
+CREATE TABLE file_sections (
+    id INTEGER PRIMARY KEY,
+    section_id INTEGER NOT NULL REFERENCES sections (id),
+    file_id INTEGER NOT NULL REFERENCES files (id),
+    name TEXT NOT NULL,
+    address INTEGER,
+    size INTEGER,
+    rule TEXT
+);
+CREATE TABLE symbols (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    address INTEGER,
+    lma INTEGER,
+    kind TEXT NOT NULL,
+    section_id INTEGER REFERENCES sections (id),
+    file_section_id INTEGER REFERENCES file_sections (id)
+);
+CREATE INDEX sections_name ON sections (name);
+CREATE INDEX sections_address ON sections (address);
+CREATE INDEX file_sections_section_id ON file_sections (section_id);
+CREATE INDEX file_sections_file_id ON file_sections (file_id);
+CREATE INDEX file_sections_name ON file_sections (name);
+CREATE INDEX file_sections_address ON file_sections (address);
+CREATE INDEX symbols_name ON symbols (name);
+CREATE INDEX symbols_address ON symbols (address);
+CREATE INDEX symbols_file_section_id ON symbols (file_section_id);
+";
+
+/// The map file as an SQLite database with the tables `sections`, `file_sections`, `symbols`,
+/// `files`, `archives` and `memory_regions`. Databases can only be written to files.
+///
+/// Addresses and sizes are stored as integers. SQLite integers are signed, so addresses from
+/// `0x8000000000000000` up read back as negative numbers.
+pub struct Sqlite;
+
+impl Exporter for Sqlite {
+    fn name(&self) -> &'static str {
+        "sqlite"
+    }
+
+    fn extension(&self) -> &'static str {
+        "sqlite"
+    }
+
+    fn write(&self, _map: &MapFile, _writer: &mut dyn Write) -> anyhow::Result<()> {
+        bail!("sqlite databases can only be written to a file")
+    }
+
+    fn export(&self, map: &MapFile, path: &Path) -> anyhow::Result<()> {
+        export(map, path)
+    }
+}
+
+/// Writes the database, replacing `path` if it exists.
+fn export(map: &MapFile, path: &Path) -> anyhow::Result<()> {
+    if path.exists() {
+        fs::remove_file(path)?;
+    }
+    let mut connection = Connection::open(path)?;
+    connection.pragma_update(None, "foreign_keys", true)?;
+    let transaction = connection.transaction()?;
+    transaction.execute_batch(SCHEMA)?;
+
+    let mut regions = HashMap::new();
+    for region in &map.memory_regions {
+        transaction
+            .prepare_cached(
+                "INSERT INTO memory_regions (name, origin, length, attributes)
+                 VALUES (?1, ?2, ?3, ?4)",
+            )?
+            .execute(params![
+                region.name,
+                integer(&region.origin),
+                integer(&region.length),
+                region.attributes
+            ])?;
+        regions.insert(&*region.name, transaction.last_insert_rowid());
+    }
+    // like the usage report, sections whose region is not named are placed by address
+    let located = located_regions(map);
+    let region_id = |section: &Section| {
+        let name = match &section.memory_region {
+            Some(name) => name,
+            None if is_non_alloc(&section.name) => return None,
+            None => {
+                &located[region_at(&located, hex_value(&section.address)?)?]
+                    .0
+                    .name
+            }
+        };
+        regions.get(&**name).copied()
+    };
+
+    let mut files = Files::default();
+    for group in &map.sections {
+        let section = &group.section;
+        transaction
+            .prepare_cached(
+                "INSERT INTO sections (name, address, size, lma, memory_region_id)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?
+            .execute(params![
+                section.name,
+                integer(&section.address),
+                integer(&section.size),
+                section.lma.map(|lma| lma as i64),
+                region_id(section),
+            ])?;
+        let section_id = transaction.last_insert_rowid();
+
+        for fsg in &group.file_section_groups {
+            let file_section = &fsg.file_section;
+            let file_id = files.id(&transaction, &file_section.file)?;
+            transaction
+                .prepare_cached(
+                    "INSERT INTO file_sections (section_id, file_id, name, address, size, rule)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                )?
+                .execute(params![
+                    section_id,
+                    file_id,
+                    file_section.section,
+                    integer(&file_section.address),
+                    integer(&file_section.size),
+                    fsg.rule,
+                ])?;
+            let file_section_id = transaction.last_insert_rowid();
+            for symbol in &fsg.symbols {
+                let lma = load_address(section, &symbol.address).map(|lma| lma as i64);
+                insert_symbol(
+                    &transaction,
+                    &symbol.name,
+                    &symbol.address,
+                    lma,
+                    SymbolKind::Object,
+                    Some(section_id),
+                    Some(file_section_id),
+                )?;
+            }
+        }
+        for assignment in group.assignments.iter().filter(|a| a.defines_symbol()) {
+            let lma = load_address(section, &assignment.address).map(|lma| lma as i64);
+            insert_symbol(
+                &transaction,
+                &assignment.lhs,
+                &assignment.address,
+                lma,
+                SymbolKind::LinkerScript,
+                Some(section_id),
+                None,
+            )?;
+        }
+    }
+    for assignment in map.assignments.iter().filter(|a| a.defines_symbol()) {
+        insert_symbol(
+            &transaction,
+            &assignment.lhs,
+            &assignment.address,
+            None,
+            SymbolKind::LinkerScript,
+            None,
+            None,
+        )?;
+    }
+    transaction.commit()?;
+    Ok(())
+}
+
+fn insert_symbol(
+    connection: &Connection,
+    name: &str,
+    address: &str,
+    lma: Option<i64>,
+    kind: SymbolKind,
+    section_id: Option<i64>,
+    file_section_id: Option<i64>,
+) -> rusqlite::Result<()> {
+    connection
+        .prepare_cached(
+            "INSERT INTO symbols (name, address, lma, kind, section_id, file_section_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )?
+        .execute(params![
+            name,
+            integer(address),
+            lma,
+            kind.as_str(),
+            section_id,
+            file_section_id,
+        ])?;
+    Ok(())
+}
+
+fn integer(number: &str) -> Option<i64> {
+    hex_value(number).map(|value| value as i64)
+}
+
+/// Ids of the rows of `files` and `archives` inserted so far, by path.
+#[derive(Default)]
+struct Files<'a> {
+    files: HashMap<&'a str, i64>,
+    archives: HashMap<&'a str, i64>,
+}
+
+impl<'a> Files<'a> {
+    /// Id of `path`, inserting it (and its archive, for `lib.a(member.o)`) when it is new.
+    fn id(&mut self, connection: &Connection, path: &'a str) -> rusqlite::Result<i64> {
+        if let Some(id) = self.files.get(path) {
+            return Ok(*id);
+        }
+        let (archive_id, member) = match archive_member(path) {
+            Some((archive, member)) => {
+                let id = match self.archives.get(archive) {
+                    Some(id) => *id,
+                    None => {
+                        connection
+                            .prepare_cached("INSERT INTO archives (path) VALUES (?1)")?
+                            .execute(params![archive])?;
+                        let id = connection.last_insert_rowid();
+                        self.archives.insert(archive, id);
+                        id
+                    }
+                };
+                (Some(id), Some(member))
+            }
+            None => (None, None),
+        };
+        connection
+            .prepare_cached("INSERT INTO files (path, archive_id, member) VALUES (?1, ?2, ?3)")?
+            .execute(params![path, archive_id, member])?;
+        let id = connection.last_insert_rowid();
+        self.files.insert(path, id);
+        Ok(id)
+    }
+}
@@ -0,0 +1,117 @@
+use std::io::{BufRead, Write};
+
+use csv::Writer;
+
+use super::Exporter;
+use crate::formats::SectionGroups;
+use crate::table::Table;
+use crate::types::MapFile;
+use crate::{assignment_record, records, section_records};
+
+/// Every symbol of the map file as a CSV row, including symbols assigned by the linker script.
+pub struct Csv;
+
+impl Exporter for Csv {
+    fn name(&self) -> &'static str {
+        "csv"
+    }
+
+    fn extension(&self) -> &'static str {
+        "csv"
+    }
+
+    fn write(&self, map: &MapFile, writer: &mut dyn Write) -> anyhow::Result<()> {
+        let mut writer = Writer::from_writer(writer);
+        for record in records(map) {
+            writer.serialize(&record)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+impl Csv {
+    /// Writes the rows of each output section as soon as `groups` has parsed it, then the rows
+    /// of symbols assigned outside of output sections, so memory does not grow with the map.
+    /// Unlike [`Exporter::write`], rows are in map order rather than sorted by name.
+    pub fn write_stream<R: BufRead>(
+        &self,
+        groups: &mut SectionGroups<R>,
+        writer: &mut dyn Write,
+    ) -> anyhow::Result<()> {
+        let mut writer = Writer::from_writer(writer);
+        for group in groups.by_ref() {
+            let group = group?;
+            for record in section_records(&group) {
+                writer.serialize(&record)?;
+            }
+        }
+        for assignment in groups.assignments().iter().filter(|a| a.defines_symbol()) {
+            writer.serialize(assignment_record(assignment, None))?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// The rows of [`Csv`] as a GitHub-flavoured Markdown table.
+pub struct Markdown;
+
+impl Exporter for Markdown {
+    fn name(&self) -> &'static str {
+        "markdown"
+    }
+
+    fn extension(&self) -> &'static str {
+        "md"
+    }
+
+    fn write(&self, map: &MapFile, writer: &mut dyn Write) -> anyhow::Result<()> {
+        write!(writer, "{}", table(map).to_markdown())?;
+        Ok(())
+    }
+}
+
+/// The rows of [`Csv`] as an aligned plain-text table.
+pub struct Text;
+
+impl Exporter for Text {
+    fn name(&self) -> &'static str {
+        "table"
+    }
+
+    fn extension(&self) -> &'static str {
+        "txt"
+    }
+
+    fn write(&self, map: &MapFile, writer: &mut dyn Write) -> anyhow::Result<()> {
+        write!(writer, "{}", table(map).to_text())?;
+        Ok(())
+    }
+}
+
+fn table(map: &MapFile) -> Table {
+    let mut table = Table::new([
+        "symbol",
+        "address",
+        "file",
+        "old_section",
+        "new_section",
+        "lma",
+        "kind",
+        "rule",
+    ]);
+    for record in records(map) {
+        table.push([
+            record.symbol,
+            record.address,
+            record.file,
+            record.old_section,
+            record.new_section,
+            record.lma.as_deref().unwrap_or(""),
+            record.kind.as_str(),
+            record.rule.unwrap_or(""),
+        ]);
+    }
+    table
+}
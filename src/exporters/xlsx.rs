@@ -0,0 +1,246 @@
+use std::io::Write;
+
+use rust_xlsxwriter::{Format, Workbook, XlsxError};
+
+use super::Exporter;
+use crate::reports::{input_usage, region_usage, section_padding};
+use crate::types::*;
+use crate::units::hex_value;
+
+/// The map file as an Excel workbook with the sheets `symbols`, `file_sections`, `sections`,
+/// `memory_regions`, `inputs` and `padding`. Every sheet has frozen headers and an autofilter.
+///
+/// Addresses and sizes are numbers rather than hex strings so they can be summed and pivoted.
+/// Excel numbers are doubles, so addresses above 2^53 lose their lowest bits.
+///
+/// A sheet holds at most [`MAX_ROWS`] rows, header included. Maps with more symbols or input
+/// sections than that are an error before anything is written; export them to sqlite or csv.
+pub struct Xlsx;
+
+/// Rows of an Excel sheet.
+pub const MAX_ROWS: usize = 1 << 20;
+
+impl Exporter for Xlsx {
+    fn name(&self) -> &'static str {
+        "xlsx"
+    }
+
+    fn extension(&self) -> &'static str {
+        "xlsx"
+    }
+
+    fn write(&self, map: &MapFile, writer: &mut dyn Write) -> anyhow::Result<()> {
+        let records = crate::records(map);
+        fits("symbols", records.len())?;
+        let file_section_count = map.sections.iter().map(|g| g.file_section_groups.len());
+        fits("file_sections", file_section_count.sum())?;
+
+        let mut workbook = Workbook::new();
+        symbols(&mut workbook, records)?;
+        file_sections(&mut workbook, map)?;
+        sections(&mut workbook, map)?;
+        memory_regions(&mut workbook, map)?;
+        inputs(&mut workbook, map)?;
+        padding(&mut workbook, map)?;
+        writer.write_all(&workbook.save_to_buffer()?)?;
+        Ok(())
+    }
+}
+
+/// Fails if a sheet called `name` with `rows` rows under its header does not fit in Excel.
+fn fits(name: &str, rows: usize) -> anyhow::Result<()> {
+    if rows >= MAX_ROWS {
+        anyhow::bail!(
+            "the {name} sheet would have {rows} rows, more than the {} an Excel sheet holds; \
+             export to sqlite or csv instead",
+            MAX_ROWS - 1
+        );
+    }
+    Ok(())
+}
+
+enum Cell<'a> {
+    Text(&'a str),
+    Number(Option<u64>),
+    Percent(f64),
+}
+
+fn number(text: &str) -> Cell<'_> {
+    Cell::Number(hex_value(text))
+}
+
+/// Adds a sheet called `name` holding `rows` under `headers`.
+fn add_sheet<'a>(
+    workbook: &mut Workbook,
+    name: &str,
+    headers: &[&str],
+    rows: impl IntoIterator<Item = Vec<Cell<'a>>>,
+) -> Result<(), XlsxError> {
+    let bold = Format::new().set_bold();
+    let percent = Format::new().set_num_format("0.00%");
+    let sheet = workbook.add_worksheet();
+    sheet.set_name(name)?;
+    for (column, header) in (0..).zip(headers) {
+        sheet.write_string_with_format(0, column, *header, &bold)?;
+    }
+    let mut last = 0;
+    for (row, cells) in (1..).zip(rows) {
+        for (column, cell) in (0..).zip(cells) {
+            match cell {
+                Cell::Text("") | Cell::Number(None) => {}
+                Cell::Text(text) => {
+                    sheet.write_string(row, column, text)?;
+                }
+                Cell::Number(Some(value)) => {
+                    sheet.write_number(row, column, value as f64)?;
+                }
+                Cell::Percent(value) => {
+                    sheet.write_number_with_format(row, column, value, &percent)?;
+                }
+            }
+        }
+        last = row;
+    }
+    sheet.set_freeze_panes(1, 0)?;
+    sheet.autofilter(0, 0, last, headers.len() as u16 - 1)?;
+    sheet.autofit();
+    Ok(())
+}
+
+fn symbols(workbook: &mut Workbook, records: Vec<Record>) -> Result<(), XlsxError> {
+    let rows = records.into_iter().map(|record| {
+        vec![
+            Cell::Text(record.symbol),
+            number(record.address),
+            Cell::Text(record.file),
+            Cell::Text(record.old_section),
+            Cell::Text(record.new_section),
+            Cell::Number(record.lma.as_deref().and_then(hex_value)),
+            Cell::Text(record.kind.as_str()),
+            Cell::Text(record.rule.unwrap_or("")),
+        ]
+    });
+    add_sheet(
+        workbook,
+        "symbols",
+        &[
+            "symbol",
+            "address",
+            "file",
+            "old_section",
+            "new_section",
+            "lma",
+            "kind",
+            "rule",
+        ],
+        rows,
+    )
+}
+
+fn file_sections(workbook: &mut Workbook, map: &MapFile) -> Result<(), XlsxError> {
+    let rows = map.sections.iter().flat_map(|group| {
+        group.file_section_groups.iter().map(|fsg| {
+            vec![
+                Cell::Text(&fsg.file_section.file),
+                Cell::Text(&fsg.file_section.section),
+                Cell::Text(&group.section.name),
+                number(&fsg.file_section.address),
+                number(&fsg.file_section.size),
+                Cell::Text(fsg.rule.as_deref().unwrap_or("")),
+            ]
+        })
+    });
+    add_sheet(
+        workbook,
+        "file_sections",
+        &[
+            "file",
+            "section",
+            "output_section",
+            "address",
+            "size",
+            "rule",
+        ],
+        rows,
+    )
+}
+
+fn sections(workbook: &mut Workbook, map: &MapFile) -> Result<(), XlsxError> {
+    let rows = map.sections.iter().map(|group| {
+        let section = &group.section;
+        vec![
+            Cell::Text(&section.name),
+            number(&section.address),
+            number(&section.size),
+            Cell::Number(section.lma),
+            Cell::Text(section.memory_region.as_deref().unwrap_or("")),
+        ]
+    });
+    add_sheet(
+        workbook,
+        "sections",
+        &["name", "address", "size", "lma", "memory_region"],
+        rows,
+    )
+}
+
+fn memory_regions(workbook: &mut Workbook, map: &MapFile) -> Result<(), XlsxError> {
+    let usage = region_usage(map);
+    let rows = map.memory_regions.iter().map(|region| {
+        let used = usage.iter().find(|u| u.name == region.name);
+        vec![
+            Cell::Text(&region.name),
+            number(&region.origin),
+            number(&region.length),
+            Cell::Number(used.map(|u| u.used)),
+            match used {
+                Some(u) => Cell::Percent(u.used as f64 / u.length.max(1) as f64),
+                None => Cell::Text(""),
+            },
+            Cell::Text(&region.attributes),
+        ]
+    });
+    add_sheet(
+        workbook,
+        "memory_regions",
+        &[
+            "name",
+            "origin",
+            "length",
+            "used",
+            "used_percent",
+            "attributes",
+        ],
+        rows,
+    )
+}
+
+fn inputs(workbook: &mut Workbook, map: &MapFile) -> Result<(), XlsxError> {
+    let rows = input_usage(map)
+        .into_iter()
+        .map(|input| vec![Cell::Text(input.path), Cell::Number(Some(input.bytes))]);
+    add_sheet(workbook, "inputs", &["path", "bytes"], rows)
+}
+
+fn padding(workbook: &mut Workbook, map: &MapFile) -> Result<(), XlsxError> {
+    let rows = section_padding(map).into_iter().map(|padding| {
+        vec![
+            Cell::Text(padding.section),
+            Cell::Number(Some(padding.size)),
+            Cell::Number(Some(padding.bytes)),
+        ]
+    });
+    add_sheet(workbook, "padding", &["section", "size", "padding"], rows)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_fits() {
+        assert!(fits("symbols", MAX_ROWS - 1).is_ok());
+        let error = fits("symbols", MAX_ROWS).unwrap_err();
+        assert!(error.to_string().contains("1048576 rows"), "{error}");
+    }
+}
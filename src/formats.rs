@@ -0,0 +1,219 @@
+use nom::IResult;
+
+use crate::error::ParseError;
+use crate::types::*;
+use crate::units::hex_value;
+
+mod ghs;
+mod gnu;
+mod ld64;
+mod lld;
+mod tasking;
+
+pub use ghs::GreenHills;
+pub use gnu::{Gnu, SectionGroups};
+pub use ld64::Ld64;
+pub use lld::{Lld, Mold};
+pub use tasking::Tasking;
+
+/// Number of bytes from the start of a map file that are handed to [`MapFormat::detect`].
+pub const SAMPLE_SIZE: usize = 64 * 1024;
+
+/// A map file dialect that can be recognised and parsed into the common model.
+pub trait MapFormat {
+    /// Short identifier of the format, e.g. `gnu`.
+    fn name(&self) -> &'static str;
+
+    /// How confident the format is that `sample` (the beginning of a map file) belongs to it,
+    /// from `0.0` (not at all) to `1.0` (certain).
+    fn detect(&self, sample: &str) -> f32;
+
+    fn parse<'a>(&self, input: &'a str) -> Result<MapFile<'a>, ParseError>;
+}
+
+/// A format recognised by [`Registry::detect`].
+#[derive(Clone, Copy)]
+pub struct Detection<'r> {
+    pub format: &'r dyn MapFormat,
+    pub confidence: f32,
+}
+
+/// The set of formats `parse` can dispatch to.
+///
+/// `Registry::default()` contains the formats built into mapper; additional formats are added
+/// with [`Registry::register`].
+pub struct Registry {
+    formats: Vec<Box<dyn MapFormat>>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self {
+            formats: Vec::new(),
+        }
+    }
+
+    /// Adds `format`, replacing any registered format with the same name.
+    pub fn register(&mut self, format: impl MapFormat + 'static) -> &mut Self {
+        self.formats.retain(|f| f.name() != format.name());
+        self.formats.push(Box::new(format));
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn MapFormat> {
+        self.formats
+            .iter()
+            .find(|f| f.name() == name)
+            .map(|f| f.as_ref())
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        self.formats.iter().map(|f| f.name()).collect()
+    }
+
+    /// Scores every registered format against the beginning of `input`, best match first.
+    /// Formats with a score of zero are left out.
+    pub fn detect_all(&self, input: &str) -> Vec<Detection<'_>> {
+        let sample = sample(input);
+        let mut detections: Vec<Detection> = self
+            .formats
+            .iter()
+            .map(|format| Detection {
+                format: format.as_ref(),
+                confidence: format.detect(sample).clamp(0.0, 1.0),
+            })
+            .filter(|d| d.confidence > 0.0)
+            .collect();
+        detections.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+        detections
+    }
+
+    pub fn detect(&self, input: &str) -> Option<Detection<'_>> {
+        self.detect_all(input).into_iter().next()
+    }
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        let mut registry = Self::new();
+        registry
+            .register(Gnu)
+            .register(GreenHills)
+            .register(Tasking)
+            .register(Ld64)
+            .register(Lld)
+            .register(Mold);
+        registry
+    }
+}
+
+pub fn sample(input: &str) -> &str {
+    let mut end = input.len().min(SAMPLE_SIZE);
+    while !input.is_char_boundary(end) {
+        end -= 1;
+    }
+    &input[..end]
+}
+
+/// Turns the result of a format's nom parser into the result of [`MapFormat::parse`], naming
+/// the format in the map file and in any error.
+pub(crate) fn finish<'a>(
+    format: &dyn MapFormat,
+    input: &'a str,
+    result: IResult<&'a str, MapFile<'a>>,
+) -> Result<MapFile<'a>, ParseError> {
+    match result {
+        Ok((_, mut map)) => {
+            map.linker = Some(format.name().into());
+            Ok(map)
+        }
+        Err(_) => Err(ParseError::new(format.name(), input, &result)),
+    }
+}
+
+/// Puts `symbol` into the file section of `group` whose address range contains it.
+/// Returns `false` if no file section contains the symbol.
+pub(crate) fn place_symbol<'a>(group: &mut SectionGroup<'a>, symbol: Symbol<'a>) -> bool {
+    let Some(address) = hex_value(&symbol.address) else {
+        return false;
+    };
+    let target = group.file_section_groups.iter_mut().find(|fsg| {
+        let start = hex_value(&fsg.file_section.address).unwrap_or(u64::MAX);
+        let size = hex_value(&fsg.file_section.size).unwrap_or(0);
+        (start..start.saturating_add(size.max(1))).contains(&address)
+    });
+    match target {
+        Some(fsg) => {
+            fsg.symbols.push(symbol);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Sorts the symbols of every file section by address, as GNU ld prints them.
+pub(crate) fn sort_symbols(groups: &mut [SectionGroup]) {
+    for fsg in groups
+        .iter_mut()
+        .flat_map(|g| g.file_section_groups.iter_mut())
+    {
+        fsg.symbols
+            .sort_by_key(|symbol| hex_value(&symbol.address).unwrap_or(u64::MAX));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct Custom;
+
+    impl MapFormat for Custom {
+        fn name(&self) -> &'static str {
+            "custom"
+        }
+
+        fn detect(&self, sample: &str) -> f32 {
+            if sample.starts_with("CUSTOM MAP") {
+                1.0
+            } else {
+                0.0
+            }
+        }
+
+        fn parse<'a>(&self, _input: &'a str) -> Result<MapFile<'a>, ParseError> {
+            Ok(MapFile::default())
+        }
+    }
+
+    #[test]
+    fn test_detect() {
+        let registry = Registry::default();
+        let name = |input| registry.detect(input).map(|d| d.format.name());
+        let gnu = "Memory Configuration\n\nLinker script and memory map\n\nLOAD main.o\n";
+        let ghs = "Green Hills Software, MULTI v4.2.4\nELXR Version 2013.5.5\n";
+        let tasking = "*   TASKING VX-toolset for TriCore   *\n";
+        assert_eq!(name(gnu), Some("gnu"));
+        assert_eq!(name(ghs), Some("ghs"));
+        assert_eq!(name(tasking), Some("tasking"));
+        let lld = "     VMA      LMA     Size Align Out     In      Symbol\n";
+        let mold = "     VMA     Size Align Out     In      Symbol\n";
+        assert_eq!(name(lld), Some("lld"));
+        assert_eq!(name(mold), Some("mold"));
+        assert_eq!(name(""), None);
+    }
+
+    #[test]
+    fn test_register() {
+        let mut registry = Registry::default();
+        registry.register(Custom);
+        assert_eq!(
+            registry.names(),
+            ["gnu", "ghs", "tasking", "ld64", "lld", "mold", "custom"]
+        );
+        let detection = registry.detect("CUSTOM MAP v1\n").unwrap();
+        assert_eq!(detection.format.name(), "custom");
+        assert_eq!(detection.confidence, 1.0);
+        assert!(registry.get("custom").is_some());
+    }
+}
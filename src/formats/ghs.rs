@@ -0,0 +1,276 @@
+use nom::branch::alt;
+use nom::bytes::complete::*;
+use nom::character::complete::*;
+use nom::combinator::opt;
+use nom::combinator::recognize;
+use nom::multi::many0;
+use nom::sequence::*;
+use nom::IResult;
+
+use super::{finish, place_symbol, sort_symbols, MapFormat};
+use crate::error::ParseError;
+use crate::lines::empty_till_end_of_line;
+use crate::types::*;
+use crate::units::*;
+
+/// Map files written by the Green Hills `elxr` linker.
+pub struct GreenHills;
+
+impl MapFormat for GreenHills {
+    fn name(&self) -> &'static str {
+        "ghs"
+    }
+
+    fn detect(&self, sample: &str) -> f32 {
+        let mut score = 0.0;
+        if sample.contains("Green Hills Software") || sample.contains("ELXR Version") {
+            score += 0.6;
+        }
+        if sample.contains("Image Summary") {
+            score += 0.2;
+        }
+        if sample.contains("Module Summary") {
+            score += 0.2;
+        }
+        score
+    }
+
+    fn parse<'a>(&self, input: &'a str) -> Result<MapFile<'a>, ParseError> {
+        finish(self, input, map_file(input))
+    }
+}
+
+fn map_file(input: &str) -> IResult<&str, MapFile<'_>> {
+    let (input, sections) = image_summary(input)?;
+    let (input, modules) = module_summary(input)?;
+    let (input, symbols) = opt(alt((
+        symbol_table("Global Symbols (sorted alphabetically)"),
+        symbol_table("Global Symbols (sorted numerically)"),
+    )))(input)?;
+
+    let mut groups: Vec<SectionGroup> = sections
+        .into_iter()
+        .map(|section| SectionGroup {
+            section,
+            file_section_groups: Vec::new(),
+            fills: Vec::new(),
+            rules: Vec::new(),
+            assignments: Vec::new(),
+        })
+        .collect();
+    for file_section in modules {
+        if let Some(group) = groups
+            .iter_mut()
+            .find(|g| g.section.name == file_section.section)
+        {
+            group.file_section_groups.push(FileSectionGroup {
+                file_section,
+                symbols: Vec::new(),
+                rule: None,
+            });
+        }
+    }
+    for (section, symbol) in symbols.unwrap_or_default() {
+        if let Some(group) = groups.iter_mut().find(|g| g.section.name == section) {
+            place_symbol(group, symbol);
+        }
+    }
+    sort_symbols(&mut groups);
+
+    Ok((
+        input,
+        MapFile {
+            sections: groups,
+            ..Default::default()
+        },
+    ))
+}
+
+fn heading(title: &'static str) -> impl FnMut(&str) -> IResult<&str, &str> {
+    move |input| {
+        recognize(tuple((
+            take_until(title),
+            tag(title),
+            empty_till_end_of_line,
+            many0(empty_till_end_of_line),
+        )))(input)
+    }
+}
+
+fn column_header_line(input: &str) -> IResult<&str, &str> {
+    recognize(tuple((
+        space0,
+        alt((tag("Section"), tag("Origin+Size"))),
+        not_line_ending,
+        line_ending,
+    )))(input)
+}
+
+/// `  .text                00001268  000000d8         216    0001488`
+fn image_summary_line(input: &str) -> IResult<&str, Section<'_>> {
+    let (input, (_, name, _, address, _, size, _, _, _, _, _)) = tuple((
+        space1,
+        section_name,
+        space1,
+        hex_digits,
+        space1,
+        hex_digits,
+        space1,
+        digit1,
+        space1,
+        hex_digits,
+        empty_till_end_of_line,
+    ))(input)?;
+    Ok((
+        input,
+        Section {
+            name: name.into(),
+            address: address.into(),
+            size: size.into(),
+            lma: None,
+            memory_region: None,
+        },
+    ))
+}
+
+/// `000002a8+000094  .vletext         libstartup.a(ind_crt1.o)`
+fn module_line(input: &str) -> IResult<&str, FileSection<'_>> {
+    let (input, (address, _, size, _, section, _, file, _)) = tuple((
+        hex_digits,
+        tag("+"),
+        hex_digits,
+        space1,
+        section_name,
+        space1,
+        path,
+        empty_till_end_of_line,
+    ))(input)?;
+    Ok((
+        input,
+        FileSection {
+            section: section.into(),
+            file: file.into(),
+            address: address.into(),
+            size: size.into(),
+        },
+    ))
+}
+
+/// ` .vletext         000002a8+000094 main`
+fn symbol_line(input: &str) -> IResult<&str, (&str, Symbol<'_>)> {
+    let (input, (_, section, _, address, _, _, _, name, _)) = tuple((
+        space1,
+        section_name,
+        space1,
+        hex_digits,
+        tag("+"),
+        hex_digits,
+        space1,
+        take_till1(|c: char| c.is_whitespace()),
+        empty_till_end_of_line,
+    ))(input)?;
+    Ok((
+        input,
+        (
+            section,
+            Symbol {
+                name: name.into(),
+                address: address.into(),
+            },
+        ),
+    ))
+}
+
+fn image_summary(input: &str) -> IResult<&str, Vec<Section<'_>>> {
+    preceded(
+        pair(heading("Image Summary"), column_header_line),
+        many0(image_summary_line),
+    )(input)
+}
+
+fn module_summary(input: &str) -> IResult<&str, Vec<FileSection<'_>>> {
+    preceded(
+        pair(heading("Module Summary"), column_header_line),
+        many0(module_line),
+    )(input)
+}
+
+fn symbol_table(title: &'static str) -> impl FnMut(&str) -> IResult<&str, Vec<(&str, Symbol<'_>)>> {
+    move |input| preceded(heading(title), many0(symbol_line))(input)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const MAP: &str = r"Green Hills Software, MULTI v4.2.4
+ELXR Version 2013.5.5
+Host OS Environment: Win32
+Load Map Wed Jun 11 10:44:04 2014
+Image Summary
+
+  Section              Base      Size(hex)    Size(dec)  SecOffs
+  .vletext             00000000  0000013c         316    0000220
+  .bss                 40000000  00000010          16    0000000
+
+Load Map Wed Jun 11 10:44:04 2014
+Module Summary
+
+  Origin+Size    Section          Module
+00000000+0000a8  .vletext         crt0.o
+000000a8+000094  .vletext         c:\ghs\lib\ppc5514\libstartup.a(ind_crt1.o)
+40000000+000010  .bss             main.o
+
+Load Map Wed Jun 11 10:44:04 2014
+Global Symbols (sorted alphabetically)
+
+ .vletext         000000a8+000094 __ghs_ind_crt1
+ .vletext         00000000+000000 _start
+ .bss             40000004+000004 counter
+
+Load Map Wed Jun 11 10:44:04 2014
+Global Symbols (sorted numerically)
+
+ .vletext         00000000+000000 _start
+ .vletext         000000a8+000094 __ghs_ind_crt1
+ .bss             40000004+000004 counter
+";
+
+    #[test]
+    fn test_module_line() {
+        assert_eq!(
+            module_line("000002a8+000094  .vletext         libstartup.a(ind_crt1.o)\r\n"),
+            Ok((
+                "",
+                FileSection {
+                    section: ".vletext".into(),
+                    file: "libstartup.a(ind_crt1.o)".into(),
+                    address: "000002a8".into(),
+                    size: "000094".into(),
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        assert!(GreenHills.detect(MAP) > 0.9);
+        let map = GreenHills.parse(MAP).unwrap();
+        let groups = map.sections;
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].section.name, ".vletext");
+        assert_eq!(groups[0].file_section_groups.len(), 2);
+        assert_eq!(groups[0].file_section_groups[0].symbols[0].name, "_start");
+        assert_eq!(
+            groups[0].file_section_groups[1].file_section.file,
+            r"c:\ghs\lib\ppc5514\libstartup.a(ind_crt1.o)"
+        );
+        assert_eq!(
+            groups[1].file_section_groups[0].symbols,
+            vec![Symbol {
+                name: "counter".into(),
+                address: "40000004".into()
+            }]
+        );
+    }
+}
@@ -0,0 +1,406 @@
+use std::collections::VecDeque;
+use std::io::{self, BufRead};
+
+use nom::branch::alt;
+use nom::combinator::opt;
+use nom::error::{Error, ErrorKind};
+use nom::multi::many0;
+use nom::sequence::*;
+use nom::IResult;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use super::{finish, MapFormat};
+use crate::error::{line_number, ParseError};
+use crate::groups::*;
+use crate::lines::*;
+use crate::types::*;
+
+/// Map files written by GNU ld.bfd and gold (`-Map`).
+pub struct Gnu;
+
+impl MapFormat for Gnu {
+    fn name(&self) -> &'static str {
+        "gnu"
+    }
+
+    fn detect(&self, sample: &str) -> f32 {
+        let markers = [
+            "Linker script and memory map",
+            "\nMemory map",
+            "Memory Configuration",
+            "Discarded input sections",
+            "Archive member included",
+            "\nLOAD ",
+        ];
+        let found = markers.iter().filter(|m| sample.contains(*m)).count();
+        found as f32 / markers.len() as f32
+    }
+
+    fn parse<'a>(&self, input: &'a str) -> Result<MapFile<'a>, ParseError> {
+        finish(self, input, map_file(input))
+    }
+}
+
+/// Anything that can follow the memory map header at the start of a line.
+fn item(input: &str) -> IResult<&str, Item<'_>> {
+    fn section_group_wrapper(input: &str) -> IResult<&str, Item<'_>> {
+        let (input, group) = section_group(input)?;
+        Ok((input, Item::Section(group)))
+    }
+
+    fn empty_section_wrapper(input: &str) -> IResult<&str, Item<'_>> {
+        let (input, section) = empty_section(input)?;
+        Ok((input, Item::EmptySection(section)))
+    }
+
+    // bfd prints the LOAD lines after the memory map when the linker script has a
+    // SECTIONS command and before it otherwise
+    fn load_line_wrapper(input: &str) -> IResult<&str, Item<'_>> {
+        let (input, path) = load_line(input)?;
+        Ok((input, Item::Input(LoadedInput { path: path.into() })))
+    }
+
+    fn output_line_wrapper(input: &str) -> IResult<&str, Item<'_>> {
+        let (input, output) = output_line(input)?;
+        Ok((input, Item::Output(output)))
+    }
+
+    fn cross_reference_table_wrapper(input: &str) -> IResult<&str, Item<'_>> {
+        let (input, references) = cross_reference_table(input)?;
+        Ok((input, Item::CrossReferences(references)))
+    }
+
+    fn assignment_line_wrapper(input: &str) -> IResult<&str, Item<'_>> {
+        let (input, assignment) = assignment_line(input)?;
+        Ok((input, Item::Assignment(assignment)))
+    }
+
+    // `/DISCARD/` has no address, so it and its rules are left outside of any section
+    fn skipped_line_wrapper(input: &str) -> IResult<&str, Item<'_>> {
+        let (input, _) = alt((
+            empty_till_end_of_line,
+            comment_line,
+            section_rule_line,
+            group_line,
+            provide_line,
+            linker_generated_line,
+        ))(input)?;
+        Ok((input, Item::Skipped))
+    }
+
+    alt((
+        section_group_wrapper,
+        load_line_wrapper,
+        empty_section_wrapper,
+        assignment_line_wrapper,
+        output_line_wrapper,
+        cross_reference_table_wrapper,
+        skipped_line_wrapper,
+    ))(input)
+}
+
+/// Memory maps are split into pieces of about this size, parsed in parallel with the
+/// `parallel` feature.
+const PIECE_SIZE: usize = 256 * 1024;
+
+fn map_file(input: &str) -> IResult<&str, MapFile<'_>> {
+    map_file_in_pieces(input, PIECE_SIZE)
+}
+
+fn map_file_in_pieces(input: &str, piece_size: usize) -> IResult<&str, MapFile<'_>> {
+    let start = input;
+    let (input, (discarded, memory_regions, marker)) = tuple((
+        opt(discarded_sections),
+        opt(memory_configuration),
+        prefix_junk,
+    ))(input)?;
+
+    let pieces = pieces(input, piece_size);
+    #[cfg(feature = "parallel")]
+    let parsed: Vec<IResult<&str, Vec<Item>>> =
+        pieces.par_iter().map(|piece| many0(item)(piece)).collect();
+    #[cfg(not(feature = "parallel"))]
+    let parsed: Vec<IResult<&str, Vec<Item>>> =
+        pieces.iter().map(|piece| many0(item)(piece)).collect();
+    let mut items = Vec::new();
+    let mut offset = 0;
+    let mut rest = &input[input.len()..];
+    for (piece, result) in pieces.iter().zip(parsed) {
+        let (piece_rest, piece_items) = result?;
+        items.extend(piece_items);
+        offset += piece.len();
+        // like a single parse, stop at the first piece that could not be parsed to its end
+        if !piece_rest.is_empty() {
+            rest = &input[offset - piece_rest.len()..];
+            break;
+        }
+    }
+    let input = rest;
+
+    let mut map = MapFile {
+        discarded: discarded.unwrap_or_default(),
+        memory_regions: memory_regions.unwrap_or_default(),
+        ..Default::default()
+    };
+    for item in items {
+        match item {
+            Item::Section(group) => map.sections.push(group),
+            Item::Input(input) => map.inputs.push(input),
+            Item::Output(output) => map.output = Some(output),
+            Item::CrossReferences(references) => map.cross_references = references,
+            Item::EmptySection(section) => map.empty_sections.push(section),
+            Item::Assignment(assignment) => map.assignments.push(assignment),
+            Item::Skipped => {}
+        }
+    }
+    // without the memory map header, sections are the only sign that this is a map at all
+    if marker.is_empty() && map.sections.is_empty() {
+        return Err(nom::Err::Error(Error::new(start, ErrorKind::Verify)));
+    }
+    if !input.trim().is_empty() {
+        map.warnings.push(Warning {
+            line: line_number(start, input),
+            message: "unrecognised line, the rest of the map file was skipped".to_string(),
+        });
+    }
+    Ok((input, map))
+}
+
+/// `input` cut into pieces of at least `size` bytes before lines starting with `.`, which can
+/// only be output section declarations, so every piece parses as it would in place. The cross
+/// reference table is left in the last piece since its symbols also start in the first column.
+fn pieces(input: &str, size: usize) -> Vec<&str> {
+    let end = input
+        .find("\nCross Reference Table")
+        .map_or(input.len(), |index| index + 1);
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    while let Some((index, _)) = input[start..end]
+        .match_indices("\n.")
+        .find(|(index, _)| index + 1 >= size)
+    {
+        pieces.push(&input[start..start + index + 1]);
+        start += index + 1;
+    }
+    pieces.push(&input[start..]);
+    pieces
+}
+
+enum Item<'a> {
+    Section(SectionGroup<'a>),
+    Input(LoadedInput<'a>),
+    Output(Output<'a>),
+    CrossReferences(Vec<CrossReference<'a>>),
+    EmptySection(EmptySection<'a>),
+    Assignment(Assignment<'a>),
+    Skipped,
+}
+
+/// The output sections of a GNU ld map file, parsed one at a time while the map is read, for
+/// maps too large to hold in memory.
+///
+/// The map is split before every line that starts in the first column, so at most one output
+/// section (with the input sections and symbols placed into it) is held at a time. Everything
+/// before the `Linker script and memory map` (or gold's `Memory map`) header is skipped, or
+/// before the first output section in maps without the header, and the cross reference table
+/// ends the memory map. Like [`Gnu::parse`], an unrecognised line
+/// ends the sections with a warning, see [`SectionGroups::warnings`].
+pub struct SectionGroups<R> {
+    reader: R,
+    /// Bytes of the line being read.
+    bytes: Vec<u8>,
+    /// First line of the next chunk, read while looking for the end of the current one.
+    next_line: String,
+    /// Number of lines read so far.
+    line: usize,
+    in_memory_map: bool,
+    done: bool,
+    groups: VecDeque<SectionGroup<'static>>,
+    assignments: Vec<Assignment<'static>>,
+    warnings: Vec<Warning>,
+}
+
+impl<R: BufRead> SectionGroups<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            bytes: Vec::new(),
+            next_line: String::new(),
+            line: 0,
+            in_memory_map: false,
+            done: false,
+            groups: VecDeque::new(),
+            assignments: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Assignments outside of any output section read so far, like [`MapFile::assignments`].
+    pub fn assignments(&self) -> &[Assignment<'static>] {
+        &self.assignments
+    }
+
+    /// Problems found so far, like [`MapFile::warnings`].
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    /// Whether the memory map was found, by its header or, in maps without one, by the first
+    /// output section; without it nothing is read.
+    pub fn found_memory_map(&self) -> bool {
+        self.in_memory_map
+    }
+
+    /// Reads the next line into `line`, returning `false` at the end of the map. Invalid UTF-8
+    /// is decoded lossily, like in [`read_map`](crate::read_map).
+    fn read_line(&mut self, line: &mut String) -> io::Result<bool> {
+        line.clear();
+        self.bytes.clear();
+        let read = self.reader.read_until(b'\n', &mut self.bytes)? > 0;
+        line.push_str(&String::from_utf8_lossy(&self.bytes));
+        self.line += read as usize;
+        Ok(read)
+    }
+
+    /// The lines up to the next line starting in the first column, with the line number of
+    /// the first, or `None` at the end of the memory map.
+    fn chunk(&mut self) -> io::Result<Option<(usize, String)>> {
+        let mut line = String::new();
+        while !self.in_memory_map {
+            if !self.read_line(&mut line)? {
+                self.warnings.push(Warning {
+                    line: self.line,
+                    message: "no memory map found".to_string(),
+                });
+                return Ok(None);
+            }
+            if let Some(marker) = ["Linker script and memory map", "Memory map"]
+                .into_iter()
+                .find(|marker| line.starts_with(marker))
+            {
+                self.next_line = line[marker.len()..].to_string();
+                self.in_memory_map = true;
+            } else if is_section_declaration(&line) {
+                // like `Gnu::parse`, maps without the header, which some gold and mold versions
+                // leave out, are read from their first output section
+                self.next_line = std::mem::take(&mut line);
+                self.in_memory_map = true;
+            }
+        }
+
+        let mut chunk = std::mem::take(&mut self.next_line);
+        let start = self.line;
+        while self.read_line(&mut line)? {
+            if !chunk.is_empty() && !line.starts_with(char::is_whitespace) {
+                self.next_line = std::mem::take(&mut line);
+                break;
+            }
+            chunk.push_str(&line);
+        }
+        match chunk.is_empty() || chunk.starts_with("Cross Reference Table") {
+            true => Ok(None),
+            false => Ok(Some((start, chunk))),
+        }
+    }
+
+    fn parse(&mut self, start: usize, chunk: &str) {
+        let (rest, items) = match many0(item)(chunk) {
+            Ok(result) => result,
+            Err(_) => (chunk, Vec::new()),
+        };
+        for item in items {
+            match item {
+                Item::Section(group) => self.groups.push_back(group.into_owned()),
+                Item::Assignment(assignment) => self.assignments.push(assignment.into_owned()),
+                _ => {}
+            }
+        }
+        if !rest.trim().is_empty() {
+            self.warnings.push(Warning {
+                line: start + line_number(chunk, rest) - 1,
+                message: "unrecognised line, the rest of the map file was skipped".to_string(),
+            });
+            self.done = true;
+        }
+    }
+}
+
+/// Whether `line` starts an output section, like `.text` rather than a path like
+/// `./libfoo.a(bar.o)` in the list of archive members.
+fn is_section_declaration(line: &str) -> bool {
+    let name = line.split_whitespace().next().unwrap_or("");
+    line.starts_with('.') && !name.contains(['/', '\\', '('])
+}
+
+impl<R: BufRead> Iterator for SectionGroups<R> {
+    type Item = io::Result<SectionGroup<'static>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(group) = self.groups.pop_front() {
+                return Some(Ok(group));
+            }
+            if self.done {
+                return None;
+            }
+            match self.chunk() {
+                Ok(Some((start, chunk))) => self.parse(start, &chunk),
+                Ok(None) => self.done = true,
+                Err(error) => {
+                    self.done = true;
+                    return Some(Err(error));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_pieces() {
+        let input = "\n.text 0x0 0x4\n .text 0x0 0x4 a.o\n.data 0x4 0x4\n";
+        assert_eq!(
+            pieces(input, 1),
+            [
+                "\n",
+                ".text 0x0 0x4\n .text 0x0 0x4 a.o\n",
+                ".data 0x4 0x4\n"
+            ]
+        );
+        assert_eq!(
+            pieces(input, 20),
+            ["\n.text 0x0 0x4\n .text 0x0 0x4 a.o\n", ".data 0x4 0x4\n"]
+        );
+        assert_eq!(pieces(input, 100), [input]);
+    }
+
+    #[test]
+    fn test_is_section_declaration() {
+        assert!(is_section_declaration(
+            ".text           0x0000000000001000        0x4\n"
+        ));
+        assert!(is_section_declaration(".rodata.long_name\n"));
+        assert!(!is_section_declaration(
+            "./libcompute.a(main.o)        app.o (compute)\n"
+        ));
+        assert!(!is_section_declaration(" .text          0x0 0x4 a.o\n"));
+    }
+
+    #[test]
+    fn test_map_file_in_pieces() {
+        let firmware = include_str!("../../tests/fixtures/firmware.bfd.map");
+        for input in [
+            firmware.to_string(),
+            firmware.replacen("\n.data", "\n???\n.data", 1),
+            include_str!("../../tests/fixtures/hello.bfd.map").to_string(),
+            include_str!("../../tests/fixtures/hello.gold.map").to_string(),
+        ] {
+            let (rest, whole) = map_file_in_pieces(&input, usize::MAX).unwrap();
+            assert_eq!(map_file_in_pieces(&input, 1).unwrap(), (rest, whole));
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use std::collections::HashMap;
+
+use nom::bytes::complete::*;
+use nom::character::complete::*;
+use nom::combinator::{map_res, opt, recognize};
+use nom::multi::many0;
+use nom::sequence::*;
+use nom::IResult;
+
+use super::{finish, MapFormat};
+use crate::error::ParseError;
+use crate::lines::empty_till_end_of_line;
+use crate::types::*;
+use crate::units::*;
+
+/// Map files written by Apple ld64 and ld-prime (`-map`).
+///
+/// ld64 does not list input sections, only atoms, so every symbol becomes a file section of
+/// its own, attributed to the object file the symbol came from.
+pub struct Ld64;
+
+impl MapFormat for Ld64 {
+    fn name(&self) -> &'static str {
+        "ld64"
+    }
+
+    fn detect(&self, sample: &str) -> f32 {
+        let markers = ["# Object files:", "# Sections:", "# Symbols:", "# Arch:"];
+        let found = markers.iter().filter(|m| sample.contains(*m)).count();
+        found as f32 / markers.len() as f32
+    }
+
+    fn parse<'a>(&self, input: &'a str) -> Result<MapFile<'a>, ParseError> {
+        finish(self, input, map_file(input))
+    }
+}
+
+fn map_file(input: &str) -> IResult<&str, MapFile<'_>> {
+    let (input, files) = preceded(
+        pair(take_until("# Object files:"), comment_line),
+        many0(object_file_line),
+    )(input)?;
+    let (input, sections) =
+        preceded(pair(comment_line, many0(comment_line)), many0(section_line))(input)?;
+    let (input, symbols) =
+        preceded(pair(comment_line, many0(comment_line)), many0(symbol_line))(input)?;
+
+    let mut groups: Vec<SectionGroup> = sections
+        .into_iter()
+        .map(|section| SectionGroup {
+            section,
+            file_section_groups: Vec::new(),
+            fills: Vec::new(),
+            rules: Vec::new(),
+            assignments: Vec::new(),
+        })
+        .collect();
+    // maps of large programs list thousands of object files
+    let paths: HashMap<usize, &str> = files.iter().copied().collect();
+    for (address, size, index, name) in symbols {
+        let Some(value) = hex_value(address) else {
+            continue;
+        };
+        let group = groups.iter_mut().find(|g| {
+            let start = hex_value(&g.section.address).unwrap_or(u64::MAX);
+            let end = start.saturating_add(hex_value(&g.section.size).unwrap_or(0));
+            (start..end).contains(&value)
+        });
+        let Some(group) = group else {
+            continue;
+        };
+        let file = paths.get(&index).copied().unwrap_or("");
+        group.file_section_groups.push(FileSectionGroup {
+            file_section: FileSection {
+                section: group.section.name.clone(),
+                file: file.into(),
+                address: address.into(),
+                size: size.into(),
+            },
+            symbols: vec![Symbol {
+                name: name.into(),
+                address: address.into(),
+            }],
+            rule: None,
+        });
+    }
+
+    let inputs = files
+        .iter()
+        .filter(|(_, file)| *file != "linker synthesized")
+        .map(|(_, path)| LoadedInput {
+            path: (*path).into(),
+        })
+        .collect();
+
+    Ok((
+        input,
+        MapFile {
+            sections: groups,
+            inputs,
+            ..Default::default()
+        },
+    ))
+}
+
+fn comment_line(input: &str) -> IResult<&str, &str> {
+    recognize(tuple((tag("#"), not_line_ending, line_ending)))(input)
+}
+
+/// `[  2] /Users/me/build/libfoo.a(bar.o)`
+fn file_index(input: &str) -> IResult<&str, usize> {
+    delimited(
+        pair(tag("["), space0),
+        map_res(digit1, str::parse),
+        tag("]"),
+    )(input)
+}
+
+fn rest_of_line(input: &str) -> IResult<&str, &str> {
+    let (input, (text, _)) = pair(not_line_ending, line_ending)(input)?;
+    Ok((input, text.trim_end()))
+}
+
+fn object_file_line(input: &str) -> IResult<&str, (usize, &str)> {
+    let (input, (index, _, file)) = tuple((file_index, space1, rest_of_line))(input)?;
+    Ok((input, (index, file)))
+}
+
+/// `0x100003F40 0x0000004B __TEXT __text`
+fn section_line(input: &str) -> IResult<&str, Section<'_>> {
+    let (input, (address, _, size, _, _segment, _, name, _)) = tuple((
+        hex_number,
+        space1,
+        hex_number,
+        space1,
+        section_name,
+        space1,
+        section_name,
+        empty_till_end_of_line,
+    ))(input)?;
+    Ok((
+        input,
+        Section {
+            name: name.into(),
+            address: address.into(),
+            size: size.into(),
+            lma: None,
+            memory_region: None,
+        },
+    ))
+}
+
+/// `0x100003F40 0x00000020 [  1] _main`
+fn symbol_line(input: &str) -> IResult<&str, (&str, &str, usize, &str)> {
+    let (input, (address, _, size, _, index, _, name)) = tuple((
+        hex_number,
+        space1,
+        hex_number,
+        space1,
+        file_index,
+        opt(space1),
+        rest_of_line,
+    ))(input)?;
+    Ok((input, (address, size, index, name)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_symbol_line() {
+        assert_eq!(
+            symbol_line("0x100003F94\t0x0000000E\t[  1] literal string: hello\\n\n"),
+            Ok((
+                "",
+                ("0x100003F94", "0x0000000E", 1, "literal string: hello\\n")
+            ))
+        );
+    }
+
+    #[test]
+    fn test_object_file_line() {
+        assert_eq!(
+            object_file_line("[ 12] /Users/me/My Project/libfoo.a(bar.o)\r\n"),
+            Ok(("", (12, "/Users/me/My Project/libfoo.a(bar.o)")))
+        );
+    }
+}
@@ -0,0 +1,293 @@
+use nom::branch::alt;
+use nom::bytes::complete::*;
+use nom::character::complete::*;
+use nom::combinator::verify;
+use nom::multi::{count, many0};
+use nom::sequence::*;
+use nom::IResult;
+
+use super::{finish, MapFormat};
+use crate::error::ParseError;
+use crate::lines::empty_till_end_of_line;
+use crate::types::*;
+use crate::units::{assignment, hex_digits, hex_value, provide};
+
+/// Map files written by LLVM lld (`-Map`).
+///
+/// lld prints one table with a column per number (`VMA`, `LMA`, `Size`, `Align`) followed by
+/// the name, indented by how deep it is: output section, input section, symbol.
+pub struct Lld;
+
+impl MapFormat for Lld {
+    fn name(&self) -> &'static str {
+        "lld"
+    }
+
+    fn detect(&self, sample: &str) -> f32 {
+        match header(sample) {
+            Some(line) if line.contains("LMA") => 1.0,
+            _ => 0.0,
+        }
+    }
+
+    fn parse<'a>(&self, input: &'a str) -> Result<MapFile<'a>, ParseError> {
+        finish(self, input, map_file(input))
+    }
+}
+
+/// Map files written by mold (`-Map`).
+///
+/// The same table as [`Lld`] without the `LMA` column.
+pub struct Mold;
+
+impl MapFormat for Mold {
+    fn name(&self) -> &'static str {
+        "mold"
+    }
+
+    fn detect(&self, sample: &str) -> f32 {
+        match header(sample) {
+            Some(line) if !line.contains("LMA") => 1.0,
+            _ => 0.0,
+        }
+    }
+
+    fn parse<'a>(&self, input: &'a str) -> Result<MapFile<'a>, ParseError> {
+        finish(self, input, map_file(input))
+    }
+}
+
+/// The first line of `sample` if it is the header of an lld or mold map.
+fn header(sample: &str) -> Option<&str> {
+    let line = sample.lines().find(|line| !line.trim().is_empty())?;
+    (line.contains("VMA") && line.contains("Out     In      Symbol")).then_some(line)
+}
+
+fn map_file(input: &str) -> IResult<&str, MapFile<'_>> {
+    let (input, columns) = header_line(input)?;
+    let (input, rows) = many0(row(columns))(input)?;
+
+    let mut groups: Vec<SectionGroup> = Vec::new();
+    let mut assignments = Vec::new();
+    for row in rows {
+        // linker script assignments share the name column, at the depth of the output
+        // section they are in or at the top for ones outside any output section
+        if let Ok(("", (lhs, expression))) = alt((provide, assignment))(row.name) {
+            let assignment = Assignment {
+                address: row.address.into(),
+                lhs: lhs.into(),
+                expression: expression.trim_end_matches(';').into(),
+            };
+            match groups.last_mut() {
+                Some(group) if row.depth != Depth::Out => group.assignments.push(assignment),
+                _ => assignments.push(assignment),
+            }
+            continue;
+        }
+        match row.depth {
+            Depth::Out => groups.push(SectionGroup {
+                section: Section {
+                    name: row.name.into(),
+                    address: row.address.into(),
+                    size: row.size.into(),
+                    lma: row
+                        .lma
+                        .and_then(hex_value)
+                        .filter(|lma| Some(*lma) != hex_value(row.address)),
+                    memory_region: None,
+                },
+                file_section_groups: Vec::new(),
+                fills: Vec::new(),
+                rules: Vec::new(),
+                assignments: Vec::new(),
+            }),
+            Depth::In => {
+                // `file.o:(.text)`, `lib.a(member.o):(.text)` or `<internal>:(.bss)`
+                let Some((file, section)) = row.name.rsplit_once(":(") else {
+                    continue;
+                };
+                if let Some(group) = groups.last_mut() {
+                    group.file_section_groups.push(FileSectionGroup {
+                        file_section: FileSection {
+                            section: section.strip_suffix(')').unwrap_or(section).into(),
+                            file: file.into(),
+                            address: row.address.into(),
+                            size: row.size.into(),
+                        },
+                        symbols: Vec::new(),
+                        rule: None,
+                    });
+                }
+            }
+            Depth::Symbol => {
+                if let Some(fsg) = groups
+                    .last_mut()
+                    .and_then(|g| g.file_section_groups.last_mut())
+                {
+                    fsg.symbols.push(Symbol {
+                        name: row.name.into(),
+                        address: row.address.into(),
+                    });
+                }
+            }
+        }
+    }
+
+    Ok((
+        input,
+        MapFile {
+            sections: groups,
+            assignments,
+            ..Default::default()
+        },
+    ))
+}
+
+#[derive(Debug, PartialEq)]
+enum Depth {
+    Out,
+    In,
+    Symbol,
+}
+
+#[derive(Debug, PartialEq)]
+struct Row<'a> {
+    address: &'a str,
+    lma: Option<&'a str>,
+    size: &'a str,
+    depth: Depth,
+    name: &'a str,
+}
+
+/// `             VMA              LMA     Size Align Out     In      Symbol`
+///
+/// Returns the number of numeric columns: lld has an `LMA` column that mold lacks. Headers
+/// without at least the `VMA`, `Size` and `Align` columns are rejected.
+fn header_line(input: &str) -> IResult<&str, usize> {
+    let (input, (_, _, columns, _, _)) = tuple((
+        multispace0,
+        tag("VMA"),
+        verify(take_until("Out"), |columns: &str| {
+            columns.split_whitespace().count() >= 2
+        }),
+        take_until("Symbol"),
+        pair(tag("Symbol"), empty_till_end_of_line),
+    ))(input)?;
+    Ok((input, 1 + columns.split_whitespace().count()))
+}
+
+/// `          201170           201170       2c    16         /tmp/a.o:(.text)`
+///
+/// The name is separated from the last number by one space for output sections, nine for
+/// input sections and seventeen for symbols.
+fn row(columns: usize) -> impl FnMut(&str) -> IResult<&str, Row<'_>> {
+    move |input| {
+        let (input, (_, numbers, indent, name, _)) = tuple((
+            space0,
+            pair(hex_digits, count(preceded(space1, hex_digits), columns - 1)),
+            space1,
+            not_line_ending,
+            line_ending,
+        ))(input)?;
+        let (address, rest) = numbers;
+        let depth = match indent.len() {
+            0..=7 => Depth::Out,
+            8..=15 => Depth::In,
+            _ => Depth::Symbol,
+        };
+        Ok((
+            input,
+            Row {
+                address,
+                lma: (rest.len() == 3).then(|| rest[0]),
+                size: rest[rest.len() - 2],
+                depth,
+                name: name.trim_end(),
+            },
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_header_line() {
+        let lld = "             VMA              LMA     Size Align Out     In      Symbol\n";
+        let mold = "               VMA       Size Align Out     In      Symbol\n";
+        assert_eq!(header_line(lld), Ok(("", 4)));
+        assert_eq!(header_line(mold), Ok(("", 3)));
+        assert!(header_line("VMA Out     In      Symbol\n").is_err());
+        assert!(header_line("VMA Size Out     In      Symbol\n").is_err());
+    }
+
+    #[test]
+    fn test_too_few_columns() {
+        for header in [
+            "VMA Out     In      Symbol",
+            "VMA Size Out     In      Symbol",
+        ] {
+            let input = format!("{header}\n          200000 .text\n");
+            assert!(Mold.detect(&input) > 0.0);
+            assert!(Mold.parse(&input).is_err());
+        }
+    }
+
+    #[test]
+    fn test_row() {
+        assert_eq!(
+            row(4)("          201170           201170       2c    16         /tmp/a.o:(.text)\n"),
+            Ok((
+                "",
+                Row {
+                    address: "201170",
+                    lma: Some("201170"),
+                    size: "2c",
+                    depth: Depth::In,
+                    name: "/tmp/a.o:(.text)"
+                }
+            ))
+        );
+        assert_eq!(
+            row(3)("            2005d0          0     0                 add\n"),
+            Ok((
+                "",
+                Row {
+                    address: "2005d0",
+                    lma: None,
+                    size: "0",
+                    depth: Depth::Symbol,
+                    name: "add"
+                }
+            ))
+        );
+        assert_eq!(
+            row(3)("            2005d0        126    16 .text\n").map(|(_, row)| row.depth),
+            Ok(Depth::Out)
+        );
+    }
+
+    #[test]
+    fn test_assignments() {
+        let input = "             VMA              LMA     Size Align Out     In      Symbol
+          200000           200000        0     1 . = 0x200000
+          200000           200000       2c    16 .text
+          200000           200000       2c    16         a.o:(.text)
+          200000           200000        0     1                 main
+          20002c           20002c        0     1         _etext = .
+          20002c           20002c        0     1 PROVIDE(end = .)
+";
+        let map = Lld.parse(input).unwrap();
+        assert_eq!(
+            map.sections[0].assignments,
+            [Assignment {
+                address: "20002c".into(),
+                lhs: "_etext".into(),
+                expression: ".".into()
+            }]
+        );
+        let globals: Vec<_> = map.assignments.iter().map(|a| &*a.lhs).collect();
+        assert_eq!(globals, [".", "end"]);
+    }
+}
@@ -0,0 +1,362 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use nom::bytes::complete::*;
+use nom::character::complete::*;
+use nom::combinator::{opt, recognize};
+use nom::multi::{many0, many1};
+use nom::sequence::*;
+use nom::IResult;
+
+use super::{finish, place_symbol, sort_symbols, MapFormat};
+use crate::error::{line_number, ParseError};
+use crate::lines::empty_till_end_of_line;
+use crate::types::*;
+use crate::units::hex_value;
+
+/// Map files written by the TASKING `ltc` linker/locator.
+pub struct Tasking;
+
+impl MapFormat for Tasking {
+    fn name(&self) -> &'static str {
+        "tasking"
+    }
+
+    fn detect(&self, sample: &str) -> f32 {
+        let mut score = 0.0;
+        if sample.contains("TASKING") {
+            score += 0.6;
+        }
+        if sample.contains("Link Result") {
+            score += 0.2;
+        }
+        if sample.contains("Locate Result") {
+            score += 0.2;
+        }
+        score
+    }
+
+    fn parse<'a>(&self, input: &'a str) -> Result<MapFile<'a>, ParseError> {
+        finish(self, input, map_file(input))
+    }
+}
+
+/// Output sections come from the locate result and their input sections from the link
+/// result, which gives the offset of each input section inside its output section.
+fn map_file(input: &str) -> IResult<&str, MapFile<'_>> {
+    let start = input;
+    let (input, links) = table("Link Result")(input)?;
+    let (input, locations) = table("* Sections")(input)?;
+    // maps of links without symbol output have no symbols table
+    let (input, symbols) = opt(table("* Symbols (sorted on name)"))(input)?;
+
+    let mut links_by_section: HashMap<&str, Vec<&Vec<&str>>> = HashMap::new();
+    for link in &links.rows {
+        if let Some(section) = links.get(link, "[out] Section") {
+            links_by_section.entry(section).or_default().push(link);
+        }
+    }
+
+    let mut groups = Vec::new();
+    let mut warnings = Vec::new();
+    for location in &locations.rows {
+        let (Some(cell), Some(address), Some(size)) = (
+            locations.get(location, "Section"),
+            locations.get(location, "Space addr"),
+            locations.get(location, "Size (MAU)"),
+        ) else {
+            continue;
+        };
+        let mut file_section_groups = Vec::new();
+        for link in links_by_section.get(cell).into_iter().flatten() {
+            let (Some(section), Some(file), Some(size)) = (
+                links.get(link, "[in] Section"),
+                links.get(link, "[in] File"),
+                links.get(link, "[in] Size (MAU)"),
+            ) else {
+                continue;
+            };
+            let offset = links.get(link, "[out] Offset").unwrap_or("0");
+            let address = offset_address(address, offset).unwrap_or_else(|| {
+                warnings.push(Warning {
+                    line: line_of(start, offset),
+                    message: format!("offset {offset} of {section} overflows {address}"),
+                });
+                "".into()
+            });
+            file_section_groups.push(FileSectionGroup {
+                file_section: FileSection {
+                    section: section_name(section).into(),
+                    file: file.into(),
+                    address,
+                    size: size.into(),
+                },
+                symbols: Vec::new(),
+                rule: None,
+            });
+        }
+        groups.push(SectionGroup {
+            section: Section {
+                name: section_name(cell).into(),
+                address: address.into(),
+                size: size.into(),
+                lma: None,
+                memory_region: None,
+            },
+            file_section_groups,
+            fills: Vec::new(),
+            rules: Vec::new(),
+            assignments: Vec::new(),
+        });
+    }
+
+    // address ranges of the sections, sorted by start, to find each symbol's section
+    let mut ranges: Vec<(u64, u64, usize)> = groups
+        .iter()
+        .enumerate()
+        .filter_map(|(index, g)| {
+            let start = hex_value(&g.section.address)?;
+            let size = hex_value(&g.section.size).unwrap_or(0);
+            Some((start, start.saturating_add(size.max(1)), index))
+        })
+        .collect();
+    ranges.sort_unstable();
+    if let Some(symbols) = &symbols {
+        for row in &symbols.rows {
+            let (Some(name), Some(address)) =
+                (symbols.get(row, "Name"), symbols.get(row, "Space addr"))
+            else {
+                continue;
+            };
+            let Some(value) = hex_value(address) else {
+                continue;
+            };
+            let after = ranges.partition_point(|(start, _, _)| *start <= value);
+            let Some((_, end, index)) = after.checked_sub(1).map(|i| ranges[i]) else {
+                continue;
+            };
+            if value < end {
+                place_symbol(
+                    &mut groups[index],
+                    Symbol {
+                        name: name.into(),
+                        address: address.into(),
+                    },
+                );
+            }
+        }
+    }
+    sort_symbols(&mut groups);
+
+    Ok((
+        input,
+        MapFile {
+            sections: groups,
+            warnings,
+            ..Default::default()
+        },
+    ))
+}
+
+/// `address` moved by `offset`, printed as wide as `address`, or `None` when the sum does not
+/// fit in 64 bits.
+fn offset_address<'a>(address: &'a str, offset: &str) -> Option<Cow<'a, str>> {
+    match (hex_value(address), hex_value(offset)) {
+        (Some(base), Some(offset)) if offset != 0 => {
+            let moved = base.checked_add(offset)?;
+            Some(format!("{moved:#0width$x}", width = address.len()).into())
+        }
+        _ => Some(address.into()),
+    }
+}
+
+/// Line of `input` that `cell`, a slice of `input`, is on.
+fn line_of(input: &str, cell: &str) -> usize {
+    let offset = cell.as_ptr() as usize - input.as_ptr() as usize;
+    line_number(input, &input[offset..])
+}
+
+/// Strips the section index TASKING appends to section names, e.g. `.text.main.main (117)`.
+fn section_name(cell: &str) -> &str {
+    match cell.rsplit_once(" (") {
+        Some((name, index)) if index.ends_with(')') => name,
+        _ => cell,
+    }
+}
+
+struct Table<'a> {
+    columns: Vec<&'a str>,
+    rows: Vec<Vec<&'a str>>,
+}
+
+impl<'a> Table<'a> {
+    fn get(&self, row: &[&'a str], column: &str) -> Option<&'a str> {
+        let index = self.columns.iter().position(|c| *c == column)?;
+        row.get(index).copied().filter(|cell| !cell.is_empty())
+    }
+}
+
+/// `| main.o    | .text.main.main (117) | 0x0000001a |`
+fn row(input: &str) -> IResult<&str, Vec<&str>> {
+    let (input, (_, _, cells, _)) = tuple((
+        space0,
+        tag("|"),
+        many1(terminated(
+            take_till(|c: char| c == '|' || c == '\r' || c == '\n'),
+            tag("|"),
+        )),
+        empty_till_end_of_line,
+    ))(input)?;
+    Ok((input, cells.into_iter().map(str::trim).collect()))
+}
+
+/// `+-------+`, `|=======|` and `|-------|` lines framing the table rows.
+fn rule_line(input: &str) -> IResult<&str, &str> {
+    recognize(tuple((
+        space0,
+        one_of("+|"),
+        take_while1(|c: char| c == '-' || c == '='),
+        one_of("+|"),
+        empty_till_end_of_line,
+    )))(input)
+}
+
+fn table(title: &'static str) -> impl FnMut(&str) -> IResult<&str, Table<'_>> {
+    move |input| {
+        let (input, (_, _, _, columns, _, rows)) = tuple((
+            take_until(title),
+            tag(title),
+            take_until("\n+"),
+            preceded(pair(line_ending, rule_line), row),
+            rule_line,
+            many0(terminated(row, many0(rule_line))),
+        ))(input)?;
+        Ok((input, Table { columns, rows }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const MAP: &str = r"*******************************************************************************
+*                                                                             *
+*                       TASKING VX-toolset for TriCore                        *
+*                       Linker / Locator v6.2r2 Build 17089                   *
+*                                                                             *
+*******************************************************************************
+
+*********************************  Link Result  *******************************
+
++-------------------------------------------------------------------------------------------------------------+
+| [in] File | [in] Section            | [in] Size (MAU) | [out] Offset | [out] Section           | [out] Size (MAU) |
+|=============================================================================================================|
+| cstart.o  | .text.cstart._START (3) | 0x0000000c      | 0x00000000   | .text.cstart._START (3) | 0x0000000c       |
+|-------------------------------------------------------------------------------------------------------------|
+| main.o    | .text.main.main (117)   | 0x0000001a      | 0x00000000   | .text.main.main (117)   | 0x0000001a       |
+|-------------------------------------------------------------------------------------------------------------|
+| main.o    | .zbss.main.counter (39) | 0x00000004      | 0x00000000   | .zbss.main.counter (39) | 0x00000004       |
++-------------------------------------------------------------------------------------------------------------+
+
+*******************************  Locate Result  *******************************
+
+* Sections
+  ========
+
++---------------------------------------------------------------------------------------------------+
+| Chip        | Group | Section                 | Size (MAU) | Space addr | Chip addr | Alignment  |
+|===================================================================================================|
+| mpe:pflash0 |       | .text.cstart._START (3) | 0x0000000c | 0x80000000 | 0x0000000 | 0x00000002 |
+| mpe:pflash0 |       | .text.main.main (117)   | 0x0000001a | 0x80000100 | 0x0000100 | 0x00000002 |
+| mpe:dspr0   |       | .zbss.main.counter (39) | 0x00000004 | 0xd0000000 | 0x0000000 | 0x00000004 |
++---------------------------------------------------------------------------------------------------+
+
+* Symbols (sorted on name)
+  ========================
+
++-------------------------------------------+
+| Name    | Space addr | Chip addr          |
+|===========================================|
+| _START  | 0x80000000 | 0x0000000          |
+| counter | 0xd0000000 | 0x0000000          |
+| main    | 0x80000100 | 0x0000100          |
++-------------------------------------------+
+";
+
+    #[test]
+    fn test_row() {
+        assert_eq!(
+            row("| main.o    | .text.main.main (117)   |\n"),
+            Ok(("", vec!["main.o", ".text.main.main (117)"]))
+        );
+        assert!(row("+---------+\n").is_err());
+    }
+
+    #[test]
+    fn test_offset_address() {
+        assert_eq!(
+            offset_address("0x80000100", "0x00000000").unwrap(),
+            "0x80000100"
+        );
+        assert_eq!(
+            offset_address("0x80000100", "0x0000001c").unwrap(),
+            "0x8000011c"
+        );
+        assert!(matches!(
+            offset_address("0x80000100", "0x0"),
+            Some(Cow::Borrowed(_))
+        ));
+        assert_eq!(offset_address("0x80000100", "0xffffffffffffffff"), None);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert!(Tasking.detect(MAP) > 0.9);
+        let map = Tasking.parse(MAP).unwrap();
+        let groups = map.sections;
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups[1].section.name, ".text.main.main");
+        assert_eq!(groups[1].section.address, "0x80000100");
+        assert_eq!(
+            groups[1].file_section_groups[0].file_section,
+            FileSection {
+                section: ".text.main.main".into(),
+                file: "main.o".into(),
+                address: "0x80000100".into(),
+                size: "0x0000001a".into(),
+            }
+        );
+        assert_eq!(
+            groups[2].file_section_groups[0].symbols,
+            vec![Symbol {
+                name: "counter".into(),
+                address: "0xd0000000".into()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_without_symbols() {
+        let input = &MAP[..MAP.find("* Symbols").unwrap()];
+        let map = Tasking.parse(input).unwrap();
+        assert_eq!(map.sections.len(), 3);
+        assert!(map.sections.iter().all(|group| group
+            .file_section_groups
+            .iter()
+            .all(|file_section| file_section.symbols.is_empty())));
+    }
+
+    #[test]
+    fn test_offset_overflow() {
+        let input = MAP.replacen(
+            "0x0000001a      | 0x00000000",
+            "0x0000001a      | 0xffffffffffffffff",
+            1,
+        );
+        let map = Tasking.parse(&input).unwrap();
+        let file_section = &map.sections[1].file_section_groups[0].file_section;
+        assert_eq!(file_section.address, "");
+        assert_eq!(map.warnings.len(), 1);
+        assert_eq!(map.warnings[0].line, 15);
+    }
+}
@@ -0,0 +1,348 @@
+//! Synthetic GNU ld map files, for benchmarks and as parser test input.
+
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+/// How the paths of input files are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathStyle {
+    /// `/home/build/obj/module1/file3.o`
+    Unix,
+    /// `C:\build\obj\module1\file3.o`
+    Windows,
+}
+
+impl PathStyle {
+    pub const ALL: [PathStyle; 2] = [PathStyle::Unix, PathStyle::Windows];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PathStyle::Unix => "unix",
+            PathStyle::Windows => "windows",
+        }
+    }
+
+    fn root(self) -> &'static str {
+        match self {
+            PathStyle::Unix => "/home/build/",
+            PathStyle::Windows => "C:\\build\\",
+        }
+    }
+
+    fn separator(self) -> char {
+        match self {
+            PathStyle::Unix => '/',
+            PathStyle::Windows => '\\',
+        }
+    }
+}
+
+impl FromStr for PathStyle {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        PathStyle::ALL
+            .into_iter()
+            .find(|style| style.name() == name)
+            .ok_or_else(|| format!("unknown path style {name}"))
+    }
+}
+
+/// What a generated map file contains.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    /// Number of output sections, cycling through `.text`, `.rodata`, `.data` and `.bss`.
+    pub sections: usize,
+    /// Number of input sections in each output section.
+    pub input_sections: usize,
+    /// Number of symbols in each input section.
+    pub symbols: usize,
+    /// Number of input files the input sections are spread over.
+    pub files: usize,
+    pub paths: PathStyle,
+    /// Whether the input files are members of archives, `libmodule1.a(file3.o)`.
+    pub archives: bool,
+    /// Seed of the sizes and alignments of input sections. The same options always generate
+    /// the same map.
+    pub seed: u64,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            sections: 8,
+            input_sections: 100,
+            symbols: 2,
+            files: 64,
+            paths: PathStyle::Unix,
+            archives: false,
+            seed: 1,
+        }
+    }
+}
+
+impl Options {
+    /// These options with `input_sections` chosen so the map is about `bytes` long.
+    pub fn with_size(mut self, bytes: u64) -> Self {
+        let length = |options: &Options| {
+            let mut counter = Counter(0);
+            write_map(options, &mut counter).expect("counting cannot fail");
+            counter.0
+        };
+        const SAMPLE: u64 = 1000;
+        self.input_sections = 0;
+        let base = length(&self);
+        self.input_sections = SAMPLE as usize;
+        let sample = length(&self).saturating_sub(base).max(1);
+        self.input_sections = (bytes.saturating_sub(base) * SAMPLE / sample).max(1) as usize;
+        self
+    }
+}
+
+const MODULES: usize = 8;
+
+/// Kinds of output sections, with the memory region they go to.
+const KINDS: [(&str, Region); 4] = [
+    ("text", Region::Flash),
+    ("rodata", Region::Flash),
+    ("data", Region::Ram),
+    ("bss", Region::Ram),
+];
+
+#[derive(Clone, Copy)]
+enum Region {
+    Flash,
+    Ram,
+}
+
+/// Splitmix64, a small generator that is good enough for section sizes.
+#[derive(Clone, Copy)]
+struct Random(u64);
+
+impl Random {
+    fn next(&mut self, below: u64) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        (z ^ (z >> 31)) % below
+    }
+}
+
+/// An input section to be written: its file, alignment padding and size.
+struct InputSection {
+    file: usize,
+    padding: u64,
+    size: u64,
+}
+
+impl InputSection {
+    /// A random input section placed at `address`, which is moved past it.
+    fn draw(random: &mut Random, address: &mut u64, files: usize) -> Self {
+        let align = 1 << random.next(4);
+        let padding = (align - *address % align) % align;
+        let size = 4 * (1 + random.next(64));
+        *address += padding + size;
+        InputSection {
+            file: random.next(files as u64) as usize,
+            padding,
+            size,
+        }
+    }
+}
+
+/// Counts the bytes written to it.
+struct Counter(u64);
+
+impl Write for Counter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+struct File<'a>(&'a Options, usize);
+
+impl fmt::Display for File<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let File(options, file) = *self;
+        let (root, separator) = (options.paths.root(), options.paths.separator());
+        let module = file % MODULES;
+        match options.archives {
+            true => write!(f, "{root}lib{separator}libmodule{module}.a(file{file}.o)"),
+            false => write!(
+                f,
+                "{root}obj{separator}module{module}{separator}file{file}.o"
+            ),
+        }
+    }
+}
+
+/// The map file described by `options`.
+pub fn generate(options: &Options) -> String {
+    let mut map = Vec::new();
+    write_map(options, &mut map).expect("writing to a vector cannot fail");
+    String::from_utf8(map).expect("generated maps are ASCII")
+}
+
+/// Like [`generate`], writing the map to `writer` a line at a time, so maps larger than memory
+/// can be generated.
+pub fn write_map(options: &Options, writer: &mut dyn Write) -> io::Result<()> {
+    let files = options.files.max(1);
+    if options.archives {
+        writeln!(
+            writer,
+            "Archive member included to satisfy reference by file (symbol)\n"
+        )?;
+        for file in 0..files {
+            let member = File(options, file).to_string();
+            // like input section names, long members push the reason onto the next line
+            match member.len() > 29 {
+                true => writeln!(writer, "{member}\n{:30}(--whole-archive)", "")?,
+                false => writeln!(writer, "{member:<30}(--whole-archive)")?,
+            }
+        }
+        writeln!(writer)?;
+    }
+    writeln!(writer, "Memory Configuration\n")?;
+    writeln!(
+        writer,
+        "Name             Origin             Length             Attributes"
+    )?;
+    writeln!(
+        writer,
+        "FLASH            0x0000000008000000 0x0000000010000000 xr"
+    )?;
+    writeln!(
+        writer,
+        "RAM              0x0000000020000000 0x0000000010000000 xrw"
+    )?;
+    writeln!(
+        writer,
+        "*default*        0x0000000000000000 0xffffffffffffffff\n"
+    )?;
+    writeln!(writer, "Linker script and memory map\n")?;
+    for file in 0..files {
+        writeln!(writer, "LOAD {}", File(options, file))?;
+    }
+
+    let mut random = Random(options.seed);
+    let mut flash = 0x0800_0000;
+    let mut ram = 0x2000_0000;
+    for index in 0..options.sections {
+        let (kind, region) = KINDS[index % KINDS.len()];
+        let name = match index < KINDS.len() {
+            true => format!(".{kind}"),
+            false => format!(".{kind}{}", index / KINDS.len()),
+        };
+        let start = match region {
+            Region::Flash => flash,
+            Region::Ram => ram,
+        };
+
+        // the header of the output section needs the sum of the sizes, so they are drawn
+        // twice from the same state rather than kept
+        let first = random;
+        let mut end = start;
+        for _ in 0..options.input_sections {
+            InputSection::draw(&mut random, &mut end, files);
+        }
+        let size = end - start;
+
+        write!(writer, "\n{name:<15} {start:#018x} {size:#10x}")?;
+        if kind == "data" {
+            write!(writer, " load address {flash:#018x}")?;
+            flash += size;
+        }
+        writeln!(writer)?;
+        let symbol_prefix = name.trim_start_matches('.');
+        writeln!(
+            writer,
+            "{:16}{start:#018x}{:16}__{symbol_prefix}_start = .",
+            "", ""
+        )?;
+        writeln!(writer, " *(.{kind} .{kind}.*)")?;
+
+        let mut random = first;
+        let mut end = start;
+        let mut address = start;
+        for number in 0..options.input_sections {
+            let input = InputSection::draw(&mut random, &mut end, files);
+            if input.padding > 0 {
+                writeln!(
+                    writer,
+                    " *fill*         {address:#018x} {:#10x} ",
+                    input.padding
+                )?;
+                address += input.padding;
+            }
+            let function = format!("{symbol_prefix}_{number}");
+            let section = format!(".{kind}.{function}");
+            // ld moves the address onto a line of its own after long names
+            match section.len() > 14 {
+                true => write!(writer, " {section}\n{:16}", "")?,
+                false => write!(writer, " {section:<15}")?,
+            }
+            writeln!(
+                writer,
+                "{address:#018x} {:#10x} {}",
+                input.size,
+                File(options, input.file)
+            )?;
+            for symbol in 0..options.symbols as u64 {
+                let offset = input.size * symbol / options.symbols as u64;
+                writeln!(
+                    writer,
+                    "{:16}{:#018x}{:16}{function}_{symbol}",
+                    "",
+                    address + offset,
+                    ""
+                )?;
+            }
+            address += input.size;
+        }
+        writeln!(
+            writer,
+            "{:16}{address:#018x}{:16}__{symbol_prefix}_end = .",
+            "", ""
+        )?;
+        match region {
+            Region::Flash => flash = address,
+            Region::Ram => ram = address,
+        }
+    }
+    writeln!(writer, "OUTPUT(generated.elf elf32-littlearm)")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_file() {
+        let mut options = Options::default();
+        assert_eq!(
+            File(&options, 11).to_string(),
+            "/home/build/obj/module3/file11.o"
+        );
+        options.paths = PathStyle::Windows;
+        options.archives = true;
+        assert_eq!(
+            File(&options, 11).to_string(),
+            "C:\\build\\lib\\libmodule3.a(file11.o)"
+        );
+    }
+
+    #[test]
+    fn test_with_size() {
+        let options = Options::default().with_size(1 << 20);
+        let length = generate(&options).len() as f64;
+        assert!((length / (1 << 20) as f64 - 1.0).abs() < 0.05, "{length}");
+    }
+}
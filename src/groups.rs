@@ -1,81 +1,140 @@
+use std::borrow::Cow;
+
 use nom::branch::alt;
-use nom::multi::many0;
+use nom::bytes::complete::{tag, take_till1, take_until};
+use nom::character::complete::{line_ending, multispace1, not_line_ending, space1};
+use nom::multi::{many0, many1};
 use nom::sequence::*;
 use nom::IResult;
 
 use crate::lines::*;
 use crate::types::*;
+use crate::units::path;
 
+/// Skips everything before the memory map. ld.bfd introduces it with "Linker script and memory
+/// map", gold with "Memory map"; maps without either marker are read from the start.
 pub fn prefix_junk(input: &str) -> IResult<&str, &str> {
-    let marker = "Linker script and memory map";
-    match input.find(marker) {
-        Some(index) => Ok((&input[(index + marker.len())..], marker)),
-        None => Err(nom::Err::Failure(nom::error::Error::new(
-            input,
-            nom::error::ErrorKind::Eof,
-        ))),
+    for marker in ["Linker script and memory map", "Memory map"] {
+        let at_line_start = |index: usize| index == 0 || input[..index].ends_with('\n');
+        if let Some((index, _)) = input.match_indices(marker).find(|(i, _)| at_line_start(*i)) {
+            return Ok((&input[(index + marker.len())..], marker));
+        }
     }
+    Ok((input, ""))
+}
+
+pub fn memory_configuration(input: &str) -> IResult<&str, Vec<MemoryRegion<'_>>> {
+    preceded(
+        tuple((
+            take_until("Memory Configuration"),
+            tag("Memory Configuration"),
+            many1(empty_till_end_of_line),
+            tag("Name"),
+            not_line_ending,
+            line_ending,
+        )),
+        many0(memory_region_line),
+    )(input)
+}
+
+/// `Discarded input sections`, listed before the memory map.
+pub fn discarded_sections(input: &str) -> IResult<&str, Vec<FileSection<'_>>> {
+    preceded(
+        tuple((
+            take_until("Discarded input sections"),
+            tag("Discarded input sections"),
+            many1(empty_till_end_of_line),
+        )),
+        many0(terminated(file_section, many0(empty_till_end_of_line))),
+    )(input)
+}
+
+/// The `Cross Reference Table` ld.bfd prints after the memory map with `--cref`.
+pub fn cross_reference_table(input: &str) -> IResult<&str, Vec<CrossReference<'_>>> {
+    preceded(
+        tuple((
+            tag("Cross Reference Table"),
+            many1(empty_till_end_of_line),
+            tag("Symbol"),
+            not_line_ending,
+            line_ending,
+        )),
+        many0(cross_reference),
+    )(input)
+}
+
+/// ```text
+/// main                                              main.o
+///                                                   startup.o
+/// ```
+fn cross_reference(input: &str) -> IResult<&str, CrossReference<'_>> {
+    let (input, (symbol, _, file, _, mut files)) = tuple((
+        take_till1(|c: char| c.is_whitespace()),
+        multispace1,
+        path,
+        empty_till_end_of_line,
+        many0(delimited(space1, path, empty_till_end_of_line)),
+    ))(input)?;
+    files.insert(0, file);
+    Ok((
+        input,
+        CrossReference {
+            symbol: symbol.into(),
+            files: files.into_iter().map(Cow::from).collect(),
+        },
+    ))
 }
 
-pub fn file_section_group(input: &str) -> IResult<&str, FileSectionGroup> {
+pub fn file_section_group(input: &str) -> IResult<&str, FileSectionGroup<'_>> {
     let (input, (file_section, symbols)) = tuple((file_section, many0(symbol_line)))(input)?;
     Ok((
         input,
         FileSectionGroup {
             file_section,
             symbols,
+            rule: None,
         },
     ))
 }
 
-pub fn section_group(input: &str) -> IResult<&str, SectionGroup> {
-    fn assignment_line_wrapper(input: &str) -> IResult<&str, Option<FileSectionGroup>> {
-        let (input, _) = assignment_line(input)?;
-        Ok((input, None))
+pub fn section_group(input: &str) -> IResult<&str, SectionGroup<'_>> {
+    fn assignment_line_wrapper(input: &str) -> IResult<&str, Option<Entry<'_>>> {
+        let (input, output) = assignment_line(input)?;
+        Ok((input, Some(Entry::Assignment(output))))
     }
 
-    fn file_section_group_wrapper(input: &str) -> IResult<&str, Option<FileSectionGroup>> {
+    fn file_section_group_wrapper(input: &str) -> IResult<&str, Option<Entry<'_>>> {
         let (input, output) = file_section_group(input)?;
-        Ok((input, Some(output)))
+        Ok((input, Some(Entry::FileSectionGroup(output))))
     }
 
-    fn section_rule_line_wrapper(input: &str) -> IResult<&str, Option<FileSectionGroup>> {
-        let (input, _) = section_rule_line(input)?;
-        Ok((input, None))
+    fn section_rule_line_wrapper(input: &str) -> IResult<&str, Option<Entry<'_>>> {
+        let (input, output) = section_rule_line(input)?;
+        Ok((input, Some(Entry::Rule(output))))
     }
 
-    fn fill_line_wrapper(input: &str) -> IResult<&str, Option<FileSectionGroup>> {
-        let (input, _) = fill_line(input)?;
-        Ok((input, None))
+    fn fill_line_wrapper(input: &str) -> IResult<&str, Option<Entry<'_>>> {
+        let (input, output) = fill_line(input)?;
+        Ok((input, Some(Entry::Fill(output))))
     }
 
-    fn empty_till_end_of_line_wrapper(input: &str) -> IResult<&str, Option<FileSectionGroup>> {
+    fn empty_till_end_of_line_wrapper(input: &str) -> IResult<&str, Option<Entry<'_>>> {
         let (input, _) = empty_till_end_of_line(input)?;
         Ok((input, None))
     }
 
-    fn empty_section_line_wrapper(input: &str) -> IResult<&str, Option<FileSectionGroup>> {
-        let (input, _) = empty_section_line(input)?;
-        Ok((input, None))
-    }
-
-    fn load_line_wrapper(input: &str) -> IResult<&str, Option<FileSectionGroup>> {
-        let (input, _) = load_line(input)?;
-        Ok((input, None))
-    }
-
-    fn output_line_wrapper(input: &str) -> IResult<&str, Option<FileSectionGroup>> {
-        let (input, _) = output_line(input)?;
-        Ok((input, None))
-    }
-
-    fn function_line_wrapper(input: &str) -> IResult<&str, Option<FileSectionGroup>> {
+    fn function_line_wrapper(input: &str) -> IResult<&str, Option<Entry<'_>>> {
         let (input, _) = function_line(input)?;
         Ok((input, None))
     }
 
-    fn comment_line_wrapper(input: &str) -> IResult<&str, Option<FileSectionGroup>> {
-        let (input, _) = comment_line(input)?;
+    fn skipped_line_wrapper(input: &str) -> IResult<&str, Option<Entry<'_>>> {
+        let (input, _) = alt((
+            provide_line,
+            relaxing_line,
+            linker_generated_line,
+            group_line,
+        ))(input)?;
         Ok((input, None))
     }
 
@@ -87,17 +146,26 @@ pub fn section_group(input: &str) -> IResult<&str, SectionGroup> {
             fill_line_wrapper,
             empty_till_end_of_line_wrapper,
             section_rule_line_wrapper,
-            empty_section_line_wrapper,
-            load_line_wrapper,
-            output_line_wrapper,
             function_line_wrapper,
-            comment_line_wrapper,
+            skipped_line_wrapper,
         ))),
     )(input)?;
 
     let mut file_section_groups = Vec::new();
-    for group in outputs.into_iter().flatten() {
-        file_section_groups.push(group);
+    let mut fills = Vec::new();
+    let mut assignments = Vec::new();
+    let mut rules = Vec::new();
+    for entry in outputs.into_iter().flatten() {
+        match entry {
+            Entry::FileSectionGroup(mut group) => {
+                // ld lists the file sections a rule placed right after the rule
+                group.rule = rules.last().cloned();
+                file_section_groups.push(group);
+            }
+            Entry::Rule(rule) => rules.push(rule.into()),
+            Entry::Fill(fill) => fills.push(fill),
+            Entry::Assignment(assignment) => assignments.push(assignment),
+        }
     }
 
     Ok((
@@ -105,14 +173,63 @@ pub fn section_group(input: &str) -> IResult<&str, SectionGroup> {
         SectionGroup {
             section,
             file_section_groups,
+            fills,
+            rules,
+            assignments,
+        },
+    ))
+}
+
+/// An output section declared by the script that received no input, with the rules that
+/// would have placed input into it.
+pub fn empty_section(input: &str) -> IResult<&str, EmptySection<'_>> {
+    let (input, (name, rules)) = pair(
+        empty_section_line,
+        many0(preceded(many0(empty_till_end_of_line), section_rule_line)),
+    )(input)?;
+    Ok((
+        input,
+        EmptySection {
+            name: name.into(),
+            rules: rules.into_iter().map(Cow::from).collect(),
         },
     ))
 }
 
+/// What an output section is made of.
+enum Entry<'a> {
+    FileSectionGroup(FileSectionGroup<'a>),
+    Fill(Fill<'a>),
+    Assignment(Assignment<'a>),
+    Rule(&'a str),
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
 
+    #[test]
+    fn test_cross_reference_table() {
+        let input = "Cross Reference Table
+
+Symbol                                            File
+_ZN9__gnu_cxx27__verbose_terminate_handlerEvv
+                                                  libstdc++.a(vterminate.o)
+main                                              main.o
+                                                  startup.o
+";
+        let (_, references) = cross_reference_table(input).unwrap();
+        assert_eq!(references.len(), 2);
+        assert_eq!(references[0].files, ["libstdc++.a(vterminate.o)"]);
+        assert_eq!(
+            references[1],
+            CrossReference {
+                symbol: "main".into(),
+                files: vec!["main.o".into(), "startup.o".into()]
+            }
+        );
+    }
+
     #[test]
     fn test_file_section_group() {
         let input = r" .text          0x00000000634519e0       0xe0 c:/toolbase/_ldata/mingw/comp_5.3.0_w64_2f/bin/../lib/gcc/x86_64-w64-mingw32/5.3.0/../../../../x86_64-w64-mingw32/lib/../lib32/libmingw32.a(lib32_libmingw32_a-atonexit.o)
@@ -123,4 +240,47 @@ mod test {
         dbg!(&result);
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_generated_section_groups() {
+        use crate::generator::{generate, Options, PathStyle};
+        use crate::units::hex_value;
+
+        for paths in PathStyle::ALL {
+            let options = Options {
+                sections: 4,
+                input_sections: 50,
+                symbols: 3,
+                paths,
+                archives: paths == PathStyle::Windows,
+                ..Default::default()
+            };
+            let map = generate(&options);
+            let start = map.find("\n.").unwrap() + 1;
+            let end = map.find("OUTPUT(").unwrap();
+            let (rest, groups) = many1(section_group)(&map[start..end]).unwrap();
+            assert_eq!(rest, "");
+            assert_eq!(groups.len(), 4);
+            for group in groups {
+                assert_eq!(group.rules.len(), 1);
+                assert_eq!(group.assignments.len(), 2);
+                assert_eq!(group.file_section_groups.len(), 50);
+                for file_section_group in &group.file_section_groups {
+                    assert_eq!(file_section_group.symbols.len(), 3);
+                    assert_eq!(file_section_group.rule, group.rules.first().cloned());
+                }
+                let padding: u64 = group
+                    .fills
+                    .iter()
+                    .map(|fill| hex_value(&fill.size).unwrap())
+                    .sum();
+                let sizes: u64 = group
+                    .file_section_groups
+                    .iter()
+                    .map(|g| hex_value(&g.file_section.size).unwrap())
+                    .sum();
+                assert_eq!(Some(padding + sizes), hex_value(&group.section.size));
+            }
+        }
+    }
 }
@@ -0,0 +1,238 @@
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read};
+use std::ops::Deref;
+use std::path::Path;
+use std::str;
+
+use memmap2::Mmap;
+
+/// The text of a map file, which the [`MapFile`](crate::MapFile) parsed from it borrows.
+///
+/// Uncompressed files are memory-mapped and parsed in place rather than copied into memory.
+/// The parsers borrow the whole text as one `str`, so the mapping is checked to be UTF-8 up
+/// front, reading it once. Maps that are not valid UTF-8, e.g. with Latin-1 paths written by
+/// Windows toolchains, are decoded lossily, each invalid byte becoming `U+FFFD`; that decoded
+/// copy is held in memory, as large as the map file. Such maps too large for memory can still
+/// be read with [`SectionGroups`](crate::formats::SectionGroups), which decodes line by line.
+pub struct MapText(Text);
+
+enum Text {
+    /// A mapping checked to be valid UTF-8.
+    Mapped(Mmap),
+    Owned(String),
+}
+
+impl Deref for MapText {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        match &self.0 {
+            // SAFETY: mappings are only kept once `str::from_utf8` accepted them
+            Text::Mapped(mapping) => unsafe { str::from_utf8_unchecked(mapping) },
+            Text::Owned(text) => text,
+        }
+    }
+}
+
+/// Reads the map file at `path`, or standard input when `path` is `-`.
+///
+/// Maps compressed with gzip, xz or zstd are decompressed, each with the cargo feature of the
+/// same name. The compression is recognised by the magic number at the start of the file, not
+/// by the file name.
+pub fn read_map(path: &Path) -> io::Result<MapText> {
+    if path == Path::new("-") {
+        let mut bytes = Vec::new();
+        io::stdin().lock().read_to_end(&mut bytes)?;
+        return Ok(owned(decompress(bytes)?));
+    }
+    let file = File::open(path)?;
+    // SAFETY: the mapping is only read; like any reader, the parse is wrong if the file is
+    // changed meanwhile. Pipes and other files that cannot be mapped are read instead.
+    let mapping = match unsafe { Mmap::map(&file) } {
+        Ok(mapping) => mapping,
+        Err(_) => return Ok(owned(decompress(fs::read(path)?)?)),
+    };
+    if is_compressed(&mapping) {
+        return Ok(owned(decompress(mapping.to_vec())?));
+    }
+    Ok(match str::from_utf8(&mapping) {
+        Ok(_) => MapText(Text::Mapped(mapping)),
+        Err(_) => MapText(Text::Owned(String::from_utf8_lossy(&mapping).into_owned())),
+    })
+}
+
+fn owned(bytes: Vec<u8>) -> MapText {
+    let text = String::from_utf8(bytes)
+        .unwrap_or_else(|error| String::from_utf8_lossy(error.as_bytes()).into_owned());
+    MapText(Text::Owned(text))
+}
+
+const GZIP: &[u8] = &[0x1f, 0x8b];
+const XZ: &[u8] = &[0xfd, b'7', b'z', b'X', b'Z', 0x00];
+const ZSTD: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+fn is_compressed(bytes: &[u8]) -> bool {
+    [GZIP, XZ, ZSTD]
+        .iter()
+        .any(|magic| bytes.starts_with(magic))
+}
+
+/// `bytes` decompressed if they start with the magic number of a supported compression format,
+/// else unchanged.
+pub fn decompress(bytes: Vec<u8>) -> io::Result<Vec<u8>> {
+    if !is_compressed(&bytes) {
+        return Ok(bytes);
+    }
+    let mut decompressed = Vec::new();
+    decompressing(io::Cursor::new(bytes))?.read_to_end(&mut decompressed)?;
+    Ok(decompressed)
+}
+
+/// Opens the map file at `path`, or standard input when `path` is `-`, to be read line by line
+/// without loading it into memory. Compressed maps are decompressed as they are read, like in
+/// [`read_map`].
+pub fn open_map(path: &Path) -> io::Result<Box<dyn BufRead>> {
+    let reader: Box<dyn Read> = match path == Path::new("-") {
+        true => Box::new(io::stdin()),
+        false => Box::new(File::open(path)?),
+    };
+    decompressing(BufReader::new(reader))
+}
+
+/// `reader` decompressed if it starts with the magic number of a supported compression format,
+/// else unchanged. Compressions whose cargo feature is disabled are an
+/// [`Unsupported`](io::ErrorKind::Unsupported) error.
+pub fn decompressing<R: BufRead + 'static>(mut reader: R) -> io::Result<Box<dyn BufRead>> {
+    let start = reader.fill_buf()?;
+    if start.starts_with(GZIP) {
+        gzip(reader)
+    } else if start.starts_with(XZ) {
+        xz(reader)
+    } else if start.starts_with(ZSTD) {
+        zstd(reader)
+    } else {
+        Ok(Box::new(reader))
+    }
+}
+
+#[cfg(feature = "gzip")]
+fn gzip<R: BufRead + 'static>(reader: R) -> io::Result<Box<dyn BufRead>> {
+    Ok(Box::new(BufReader::new(flate2::bufread::GzDecoder::new(
+        reader,
+    ))))
+}
+
+#[cfg(not(feature = "gzip"))]
+fn gzip<R: BufRead + 'static>(_: R) -> io::Result<Box<dyn BufRead>> {
+    Err(unsupported("gzip"))
+}
+
+#[cfg(feature = "xz")]
+fn xz<R: BufRead + 'static>(reader: R) -> io::Result<Box<dyn BufRead>> {
+    Ok(Box::new(BufReader::new(xz2::bufread::XzDecoder::new(
+        reader,
+    ))))
+}
+
+#[cfg(not(feature = "xz"))]
+fn xz<R: BufRead + 'static>(_: R) -> io::Result<Box<dyn BufRead>> {
+    Err(unsupported("xz"))
+}
+
+#[cfg(feature = "zstd")]
+fn zstd<R: BufRead + 'static>(reader: R) -> io::Result<Box<dyn BufRead>> {
+    Ok(Box::new(BufReader::new(
+        zstd::stream::read::Decoder::with_buffer(reader)?,
+    )))
+}
+
+#[cfg(not(feature = "zstd"))]
+fn zstd<R: BufRead + 'static>(_: R) -> io::Result<Box<dyn BufRead>> {
+    Err(unsupported("zstd"))
+}
+
+#[cfg(not(all(feature = "gzip", feature = "xz", feature = "zstd")))]
+fn unsupported(compression: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("the map is compressed with {compression}, which this mapper was built without"),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const MAP: &[u8] = b"Linker script and memory map\n";
+
+    #[test]
+    fn test_decompress() {
+        assert_eq!(decompress(MAP.to_vec()).unwrap(), MAP);
+        assert!(decompress(GZIP.to_vec()).is_err());
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_gzip() {
+        use std::io::Write;
+
+        let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gzip.write_all(MAP).unwrap();
+        assert_eq!(decompress(gzip.finish().unwrap()).unwrap(), MAP);
+    }
+
+    #[cfg(feature = "xz")]
+    #[test]
+    fn test_xz() {
+        use std::io::Write;
+
+        let mut xz = xz2::write::XzEncoder::new(Vec::new(), 6);
+        xz.write_all(MAP).unwrap();
+        assert_eq!(decompress(xz.finish().unwrap()).unwrap(), MAP);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_zstd() {
+        assert_eq!(decompress(zstd::encode_all(MAP, 0).unwrap()).unwrap(), MAP);
+    }
+
+    #[cfg(not(feature = "xz"))]
+    #[test]
+    fn test_unsupported() {
+        let error = decompress(XZ.to_vec()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn test_read_map() {
+        let path = std::env::temp_dir().join(format!("mapper-latin1-{}.map", std::process::id()));
+        fs::write(&path, b"LOAD C:\\Users\\J\xfcrgen\\main.o\n").unwrap();
+        let text = read_map(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(&*text.unwrap(), "LOAD C:\\Users\\J\u{fffd}rgen\\main.o\n");
+
+        let path = std::env::temp_dir().join(format!("mapper-mapped-{}.map", std::process::id()));
+        fs::write(&path, MAP).unwrap();
+        let text = read_map(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(text.0, Text::Mapped(_)));
+        assert_eq!(text.as_bytes(), MAP);
+    }
+
+    #[test]
+    fn test_decompressing() {
+        #[cfg(feature = "zstd")]
+        let inputs = [zstd::encode_all(MAP, 0).unwrap(), MAP.to_vec()];
+        #[cfg(not(feature = "zstd"))]
+        let inputs = [MAP.to_vec()];
+        for bytes in inputs {
+            let mut map = String::new();
+            decompressing(io::Cursor::new(bytes))
+                .unwrap()
+                .read_to_string(&mut map)
+                .unwrap();
+            assert_eq!(map.as_bytes(), MAP);
+        }
+    }
+}
@@ -1,57 +1,151 @@
 use std::fs;
 use std::path::Path;
 
-use csv::Writer;
-use nom::branch::alt;
-use nom::multi::many0;
-use nom::sequence::*;
-use nom::IResult;
+#[cfg(feature = "schema")]
+use schemars::schema::RootSchema;
+#[cfg(feature = "schema")]
+use schemars::schema_for;
+use serde::Deserialize;
 
+mod error;
+pub mod exporters;
+pub mod formats;
+pub mod generator;
 mod groups;
+mod input;
 mod lines;
+pub mod reports;
+mod table;
 mod types;
 mod units;
 
-use groups::*;
-use lines::*;
-use types::*;
+pub use error::ParseError;
+pub use exporters::{Exporter, Exporters};
+pub use formats::{Detection, MapFormat, Registry};
+pub use input::{decompress, decompressing, open_map, read_map, MapText};
+pub use table::Table;
+pub use types::*;
+use units::hex_value;
 
-pub fn parse(input: &str) -> IResult<&str, Vec<SectionGroup>> {
-    preceded(
-        pair(
-            prefix_junk,
-            many0(alt((empty_till_end_of_line, assignment_line, load_line))),
-        ),
-        many0(section_group),
-    )(input)
+impl<'a> MapFile<'a> {
+    /// Parses a map file, detecting which of the built-in formats it is in.
+    /// Map files that are not recognised are parsed as GNU ld maps, so text that is not a map
+    /// at all fails like a GNU ld map with neither a memory map header nor sections.
+    pub fn parse(input: &'a str) -> Result<Self, ParseError> {
+        Self::parse_with(&Registry::default(), input)
+    }
+
+    /// Like [`MapFile::parse`], detecting the format among the formats of `registry`.
+    pub fn parse_with(registry: &Registry, input: &'a str) -> Result<Self, ParseError> {
+        match registry.detect(input) {
+            Some(detection) => detection.format.parse(input),
+            None => formats::Gnu.parse(input),
+        }
+    }
 }
 
-pub fn to_json(info: &Vec<SectionGroup>, path: &Path) -> anyhow::Result<()> {
-    let content = serde_json::to_string_pretty(info)?;
-    fs::write(path, content)?;
-    Ok(())
+/// Load address of `address` inside `section`, if the section is loaded elsewhere than it runs.
+fn load_address(section: &Section, address: &str) -> Option<u64> {
+    let offset = hex_value(address)?.checked_sub(hex_value(&section.address)?)?;
+    section.lma?.checked_add(offset)
 }
 
-pub fn to_csv(info: &Vec<SectionGroup>, path: &Path) -> anyhow::Result<()> {
-    let mut records = Vec::new();
-    for section_group in info {
-        for file_section_group in &section_group.file_section_groups {
-            for symbol in &file_section_group.symbols {
-                records.push(Record {
-                    symbol: symbol.name,
-                    address: symbol.address,
-                    file: file_section_group.file_section.file,
-                    old_section: file_section_group.file_section.section,
-                    new_section: section_group.section.name,
-                });
-            }
-        }
+fn assignment_record<'a>(
+    assignment: &'a Assignment<'a>,
+    section: Option<&'a Section<'a>>,
+) -> Record<'a> {
+    Record {
+        symbol: &assignment.lhs,
+        address: &assignment.address,
+        file: "",
+        old_section: "",
+        new_section: section.map_or("", |s| &s.name),
+        lma: section
+            .and_then(|s| load_address(s, &assignment.address))
+            .map(|lma| format!("{lma:#x}")),
+        kind: SymbolKind::LinkerScript,
+        rule: None,
+    }
+}
+
+/// Version of the JSON layout written by [`to_json`]. It is increased whenever a change to the
+/// layout could break a consumer, e.g. a field is removed, renamed or changes type.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// JSON Schema of the document written by [`to_json`].
+#[cfg(feature = "schema")]
+pub fn schema() -> RootSchema {
+    schema_for!(Export)
+}
+
+/// Writes the whole map file, metadata included, as JSON.
+pub fn to_json(map: &MapFile, path: &Path) -> anyhow::Result<()> {
+    exporters::Json.export(map, path)
+}
+
+/// Reads back a map file written by [`to_json`], without needing the original map file.
+/// Documents written by a newer mapper, with a higher schema version, are rejected.
+pub fn from_json(path: &Path) -> anyhow::Result<MapFile<'static>> {
+    #[derive(Deserialize)]
+    struct Version {
+        #[serde(default)]
+        schema_version: u32,
+    }
+
+    let content = fs::read_to_string(path)?;
+    let Version { schema_version } = serde_json::from_str(&content)?;
+    if schema_version > SCHEMA_VERSION {
+        anyhow::bail!(
+            "{} has schema version {schema_version}, this mapper reads up to {SCHEMA_VERSION}",
+            path.display()
+        );
+    }
+    let map: MapFile = serde_json::from_str(&content)?;
+    Ok(map.into_owned())
+}
+
+/// Every symbol of the map file, including symbols assigned by the linker script, sorted by
+/// name.
+fn records<'a>(map: &'a MapFile<'a>) -> Vec<Record<'a>> {
+    let mut records: Vec<Record> = map.sections.iter().flat_map(section_records).collect();
+    for assignment in map.assignments.iter().filter(|a| a.defines_symbol()) {
+        records.push(assignment_record(assignment, None));
     }
     records.sort_unstable_by_key(|r| r.symbol);
+    records
+}
 
-    let mut writer = Writer::from_path(path)?;
-    for record in records {
-        writer.serialize(&record)?;
+/// The symbols of one output section, including symbols assigned by the linker script, in map
+/// order.
+fn section_records<'a>(section_group: &'a SectionGroup<'a>) -> Vec<Record<'a>> {
+    let mut records = Vec::new();
+    for file_section_group in &section_group.file_section_groups {
+        for symbol in &file_section_group.symbols {
+            records.push(Record {
+                symbol: &symbol.name,
+                address: &symbol.address,
+                file: &file_section_group.file_section.file,
+                old_section: &file_section_group.file_section.section,
+                new_section: &section_group.section.name,
+                lma: load_address(&section_group.section, &symbol.address)
+                    .map(|lma| format!("{lma:#x}")),
+                kind: SymbolKind::Object,
+                rule: file_section_group.rule.as_deref(),
+            });
+        }
     }
-    Ok(())
+    for assignment in section_group
+        .assignments
+        .iter()
+        .filter(|a| a.defines_symbol())
+    {
+        records.push(assignment_record(assignment, Some(&section_group.section)));
+    }
+    records
+}
+
+/// Writes every symbol of the map file as a CSV row, including symbols assigned by the linker
+/// script.
+pub fn to_csv(map: &MapFile, path: &Path) -> anyhow::Result<()> {
+    exporters::Csv.export(map, path)
 }
@@ -1,8 +1,10 @@
+use std::borrow::Cow;
+
 use nom::branch::alt;
 use nom::bytes::complete::*;
 use nom::character::complete::*;
-use nom::combinator::opt;
 use nom::combinator::recognize;
+use nom::combinator::{not, opt};
 use nom::multi::many1;
 use nom::sequence::*;
 use nom::IResult;
@@ -14,48 +16,137 @@ pub fn empty_till_end_of_line(input: &str) -> IResult<&str, &str> {
     recognize(pair(space0, line_ending))(input)
 }
 
-pub fn assignment_line(input: &str) -> IResult<&str, &str> {
-    recognize(tuple((
+/// `0x0000000020000000                _sdata = .` or an applied `PROVIDE (end = .)`.
+pub fn assignment_line(input: &str) -> IResult<&str, Assignment<'_>> {
+    let (input, (_, _, address, _, _, (lhs, expression), _)) = tuple((
         space1,
         opt(tag("[")),
         address,
         opt(tag("]")),
         space1,
-        assignment,
+        alt((provide, assignment)),
         line_ending,
-    )))(input)
+    ))(input)?;
+    Ok((
+        input,
+        Assignment {
+            address: address.into(),
+            lhs: lhs.into(),
+            expression: expression.into(),
+        },
+    ))
 }
 
 pub fn load_line(input: &str) -> IResult<&str, &str> {
-    recognize(tuple((tag("LOAD"), space1, path, empty_till_end_of_line)))(input)
+    let (input, (_, _, path, _)) =
+        tuple((tag("LOAD"), space1, path, empty_till_end_of_line))(input)?;
+    Ok((input, path))
 }
 
-pub fn output_line(input: &str) -> IResult<&str, &str> {
-    recognize(tuple((tag("OUTPUT"), not_line_ending, line_ending)))(input)
+/// `START GROUP` and `END GROUP` around the `LOAD` lines of an archive group.
+pub fn group_line(input: &str) -> IResult<&str, &str> {
+    recognize(tuple((
+        alt((tag("START GROUP"), tag("END GROUP"))),
+        empty_till_end_of_line,
+    )))(input)
+}
+
+/// `[!provide]   PROVIDE (__etext = .)`, a `PROVIDE` that was not needed by the link.
+pub fn provide_line(input: &str) -> IResult<&str, &str> {
+    recognize(tuple((
+        space1,
+        tag("[!provide]"),
+        not_line_ending,
+        line_ending,
+    )))(input)
 }
 
-pub fn fill_line(input: &str) -> IResult<&str, &str> {
+/// `0x2c (size before relaxing)` following a file section.
+pub fn relaxing_line(input: &str) -> IResult<&str, &str> {
     recognize(tuple((
         space1,
-        tag("*fill*"),
+        hex_number,
+        space1,
+        tag("(size before relaxing)"),
+        empty_till_end_of_line,
+    )))(input)
+}
+
+/// Data created by gold itself rather than taken from an input file, e.g. ` ** merge strings`.
+/// Long descriptions push the address and size onto the next line.
+pub fn linker_generated_line(input: &str) -> IResult<&str, &str> {
+    recognize(tuple((
+        space1,
+        tag("** "),
+        not_line_ending,
+        line_ending,
+        opt(tuple((
+            space1,
+            address,
+            space1,
+            hex_number,
+            empty_till_end_of_line,
+        ))),
+    )))(input)
+}
+
+/// `OUTPUT(hello elf64-x86-64)`
+pub fn output_line(input: &str) -> IResult<&str, Output<'_>> {
+    let (input, (_, file, _, format, _, _)) = tuple((
+        tag("OUTPUT("),
+        path,
+        space1,
+        take_till1(|c: char| c == ')' || c.is_whitespace()),
+        tag(")"),
+        empty_till_end_of_line,
+    ))(input)?;
+    Ok((
+        input,
+        Output {
+            file: file.into(),
+            format: format.into(),
+        },
+    ))
+}
+
+/// `*fill*` from ld.bfd, `** fill` and `** zero fill` from gold. ld.bfd prints the fill
+/// pattern after the size when it is not zero.
+pub fn fill_line(input: &str) -> IResult<&str, Fill<'_>> {
+    let (input, (_, _, _, address, _, size, _, pattern, _)) = tuple((
+        space1,
+        alt((tag("*fill*"), tag("** fill"), tag("** zero fill"))),
         space1,
         address,
         space1,
         hex_number,
         space0,
-        digit0,
+        opt(hex_digits),
         empty_till_end_of_line,
-    )))(input)
+    ))(input)?;
+    Ok((
+        input,
+        Fill {
+            address: address.into(),
+            size: size.into(),
+            pattern: pattern.map(Cow::from),
+        },
+    ))
 }
 
-pub fn symbol_line(input: &str) -> IResult<&str, Symbol> {
+pub fn symbol_line(input: &str) -> IResult<&str, Symbol<'_>> {
     let (input, (_, address, _, sym, _)) =
         tuple((space1, address, space1, symbol, empty_till_end_of_line))(input)?;
-    Ok((input, Symbol { name: sym, address }))
+    Ok((
+        input,
+        Symbol {
+            name: sym.into(),
+            address: address.into(),
+        },
+    ))
 }
 
-pub fn section_declaration(input: &str) -> IResult<&str, Section> {
-    let (input, (sec_name, _, _, _, addr, _, size, _, _)) = tuple((
+pub fn section_declaration(input: &str) -> IResult<&str, Section<'_>> {
+    let (input, (sec_name, _, memory_region, _, addr, _, size, lma, _)) = tuple((
         section_name,
         space0,
         opt(preceded(
@@ -66,25 +157,53 @@ pub fn section_declaration(input: &str) -> IResult<&str, Section> {
         address,
         space1,
         hex_number,
-        opt(tuple((space1, tag("load address"), space1, hex_number))),
+        opt(preceded(
+            tuple((space1, tag("load address"), space1)),
+            hex_number,
+        )),
         empty_till_end_of_line,
     ))(input)?;
     Ok((
         input,
         Section {
-            name: sec_name,
-            address: addr,
-            size,
+            name: sec_name.into(),
+            address: addr.into(),
+            size: size.into(),
+            lma: lma.and_then(hex_value),
+            memory_region: memory_region.map(Cow::from),
+        },
+    ))
+}
+
+/// `FLASH            0x0000000008000000 0x0000000000010000 xr` in the memory configuration.
+pub fn memory_region_line(input: &str) -> IResult<&str, MemoryRegion<'_>> {
+    let (input, (name, _, origin, _, length, _, attributes, _)) = tuple((
+        alt((tag("*default*"), identifier)),
+        space1,
+        hex_number,
+        space1,
+        hex_number,
+        space0,
+        alphanumeric0,
+        empty_till_end_of_line,
+    ))(input)?;
+    Ok((
+        input,
+        MemoryRegion {
+            name: name.into(),
+            origin: origin.into(),
+            length: length.into(),
+            attributes: attributes.into(),
         },
     ))
 }
 
 pub fn section_rule_line(input: &str) -> IResult<&str, &str> {
     let (input, (_, rule, _, _)) = tuple((space1, section_rule, space0, line_ending))(input)?;
-    Ok((input, rule))
+    Ok((input, rule.trim_end()))
 }
 
-pub fn file_section(input: &str) -> IResult<&str, FileSection> {
+pub fn file_section(input: &str) -> IResult<&str, FileSection<'_>> {
     let (input, (_, sec_name, _, addr, _, size, _, file, _)) = tuple((
         space1,
         section_name,
@@ -99,16 +218,21 @@ pub fn file_section(input: &str) -> IResult<&str, FileSection> {
     Ok((
         input,
         FileSection {
-            section: sec_name,
-            file,
-            address: addr,
-            size,
+            section: sec_name.into(),
+            file: file.into(),
+            address: addr.into(),
+            size: size.into(),
         },
     ))
 }
 
+/// An output section name with nothing after it. A name followed by an address on the next
+/// line is a long section declaration wrapped by the linker, not an empty section.
 pub fn empty_section_line(input: &str) -> IResult<&str, &str> {
-    recognize(pair(section_name, empty_till_end_of_line))(input)
+    terminated(
+        section_name,
+        pair(empty_till_end_of_line, not(pair(space1, address))),
+    )(input)
 }
 
 pub fn function_line(input: &str) -> IResult<&str, &str> {
@@ -129,6 +253,7 @@ pub fn function_line(input: &str) -> IResult<&str, &str> {
     )))(input)
 }
 
+/// `/DISCARD/`, the pseudo output section for input sections that are thrown away.
 pub fn comment_line(input: &str) -> IResult<&str, &str> {
     recognize(tuple((
         tag("/"),
@@ -142,10 +267,66 @@ pub fn comment_line(input: &str) -> IResult<&str, &str> {
 mod test {
     use super::*;
 
+    #[test]
+    fn test_empty_section_line() {
+        assert!(empty_section_line(".note.GNU-stack\n *(.note.GNU-stack)\n").is_ok());
+        assert!(empty_section_line(
+            ".note.gnu.property\n                0x0000000000000290       0x20\n"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_output_line() {
+        assert_eq!(
+            output_line("OUTPUT(hello_bfd elf64-x86-64)\n"),
+            Ok((
+                "",
+                Output {
+                    file: "hello_bfd".into(),
+                    format: "elf64-x86-64".into()
+                }
+            ))
+        );
+        assert_eq!(
+            output_line("OUTPUT(c:/build/app.exe pei-i386)\r\n").map(|(_, o)| o.format),
+            Ok("pei-i386".into())
+        );
+    }
+
     #[test]
     fn test_fill_line() {
         assert!(fill_line(" *fill*         0x0000000063b75c1c        0x4 \n").is_ok());
-        assert!(fill_line(" *fill*         0x0001054d        0x3 00\n").is_ok());
+        assert_eq!(
+            fill_line(" *fill*         0x0001054d        0x3 00\n"),
+            Ok((
+                "",
+                Fill {
+                    address: "0x0001054d".into(),
+                    size: "0x3".into(),
+                    pattern: Some("00".into())
+                }
+            ))
+        );
+        assert!(fill_line(" ** fill        0x00000000000005f2        0xe\n").is_ok());
+        assert!(fill_line(" ** zero fill   0x0000000000000019        0x3\n").is_ok());
+    }
+
+    #[test]
+    fn test_linker_generated_line() {
+        assert!(linker_generated_line(" ** GOT PLT     0x0000000000001fe8       0x28\n").is_ok());
+        let input = " ** merge strings\n                0x0000000000000708        0x7\n";
+        assert_eq!(linker_generated_line(input), Ok(("", input)));
+    }
+
+    #[test]
+    fn test_provide_line() {
+        let input = "                [!provide]                        PROVIDE (__etext = .)\n";
+        assert!(provide_line(input).is_ok());
+        assert!(relaxing_line(
+            "                                         0x2c (size before relaxing)\n"
+        )
+        .is_ok());
     }
 
     #[test]
@@ -157,8 +338,8 @@ mod test {
             Ok((
                 "",
                 Symbol {
-                    name: "B_sldmnws",
-                    address: "0x6000016c"
+                    name: "B_sldmnws".into(),
+                    address: "0x6000016c".into()
                 }
             ))
         );
@@ -167,8 +348,8 @@ mod test {
             Ok((
                 "",
                 Symbol {
-                    name: "_imp__StackWalk@36",
-                    address: "0x000000006711f270"
+                    name: "_imp__StackWalk@36".into(),
+                    address: "0x000000006711f270".into()
                 }
             ))
         );
@@ -178,7 +359,7 @@ mod test {
     fn test_load_line() {
         let input = "LOAD c:/toolbase/_ldata/mingw/comp_5.3.0_w64_2f/bin/../lib/gcc/x86_64-w64-mingw32/5.3.0/32/crtbegin.o\r\n";
         let result: Result<(&str, &str), nom::Err<nom::error::Error<&str>>> = load_line(input);
-        assert!(result.is_ok());
+        assert_eq!(result, Ok(("", "c:/toolbase/_ldata/mingw/comp_5.3.0_w64_2f/bin/../lib/gcc/x86_64-w64-mingw32/5.3.0/32/crtbegin.o")));
     }
 
     #[test]
@@ -188,6 +369,19 @@ mod test {
         let result = assignment_line(input);
         assert!(result.is_ok());
         println!("{:?}", result.unwrap().0);
+        assert_eq!(
+            assignment_line(
+                "                0x000000000000114d                PROVIDE (etext = .)\n"
+            ),
+            Ok((
+                "",
+                Assignment {
+                    address: "0x000000000000114d".into(),
+                    lhs: "etext".into(),
+                    expression: ".".into()
+                }
+            ))
+        );
     }
 
     #[test]
@@ -205,9 +399,11 @@ mod test {
             Ok((
                 "",
                 Section {
-                    name: ".data.SWRESET.PRAM3",
-                    address: "0x000e0000",
-                    size: "0x0"
+                    name: ".data.SWRESET.PRAM3".into(),
+                    address: "0x000e0000".into(),
+                    size: "0x0".into(),
+                    lma: None,
+                    memory_region: Some("*default*".into())
                 }
             ))
         );
@@ -216,9 +412,11 @@ mod test {
             Ok((
                 "",
                 Section {
-                    name: ".flashConfigData_empty",
-                    address: "0x800a8e34",
-                    size: "0x11cc"
+                    name: ".flashConfigData_empty".into(),
+                    address: "0x800a8e34".into(),
+                    size: "0x11cc".into(),
+                    lma: None,
+                    memory_region: Some("flashConfigArea".into())
                 }
             ))
         );
@@ -227,12 +425,40 @@ mod test {
             Ok((
                 "",
                 Section {
-                    name: ".text",
-                    address: "0x00000000632c1000",
-                    size: "0x762200"
+                    name: ".text".into(),
+                    address: "0x00000000632c1000".into(),
+                    size: "0x762200".into(),
+                    lma: None,
+                    memory_region: None
                 }
             ))
         );
+        assert_eq!(
+            section_declaration(
+                ".data           0x0000000020000000        0xc load address 0x00000000080000c8\n"
+            )
+            .map(|(_, section)| section.lma),
+            Ok(Some(0x80000c8))
+        );
+    }
+
+    #[test]
+    fn test_memory_region_line() {
+        assert_eq!(
+            memory_region_line("FLASH            0x0000000008000000 0x0000000000010000 xr\n"),
+            Ok((
+                "",
+                MemoryRegion {
+                    name: "FLASH".into(),
+                    origin: "0x0000000008000000".into(),
+                    length: "0x0000000000010000".into(),
+                    attributes: "xr".into()
+                }
+            ))
+        );
+        assert!(
+            memory_region_line("*default*        0x0000000000000000 0xffffffffffffffff\n").is_ok()
+        );
     }
 
     #[test]
@@ -248,10 +474,10 @@ mod test {
 ";
         let input5 = " .text          0x00000000634519e0       0xe0 c:/toolbase/_ldata/mingw/comp_5.3.0_w64_2f/bin/../lib/gcc/x86_64-w64-mingw32/5.3.0/../../../../x86_64-w64-mingw32/lib/../lib32/libmingw32.a(lib32_libmingw32_a-atonexit.o)\n";
         assert_eq!(file_section(input1), Ok(("", FileSection {
-            section: ".text",
-            address: "0x00000000632c1000",
-            size: "0x450",
-            file: "c:/toolbase/_ldata/mingw/comp_5.3.0_w64_2f/bin/../lib/gcc/x86_64-w64-mingw32/5.3.0/../../../../x86_64-w64-mingw32/lib/../lib32/dllcrt2.o"
+            section: ".text".into(),
+            address: "0x00000000632c1000".into(),
+            size: "0x450".into(),
+            file: "c:/toolbase/_ldata/mingw/comp_5.3.0_w64_2f/bin/../lib/gcc/x86_64-w64-mingw32/5.3.0/../../../../x86_64-w64-mingw32/lib/../lib32/dllcrt2.o".into()
         })));
 
         assert_eq!(
@@ -259,10 +485,11 @@ mod test {
             Ok((
                 "",
                 FileSection {
-                    section: ".bss.a1..DFES_stOutstate",
-                    address: "0x001b7d5b",
-                    size: "0x1",
+                    section: ".bss.a1..DFES_stOutstate".into(),
+                    address: "0x001b7d5b".into(),
+                    size: "0x1".into(),
                     file: "_gen/swb/filegroup/linker/libs/_prj_link_archive.a(dfes_outstate.o)"
+                        .into()
                 }
             ))
         );
@@ -272,10 +499,10 @@ mod test {
             Ok((
                 "",
                 FileSection {
-                    section: ".zbss.SWRESET.ZRAM3_mcop",
-                    address: "0x40000090",
-                    size: "0x170",
-                    file: "_gen/swb/module/build/reloc_vared.elf"
+                    section: ".zbss.SWRESET.ZRAM3_mcop".into(),
+                    address: "0x40000090".into(),
+                    size: "0x170".into(),
+                    file: "_gen/swb/module/build/reloc_vared.elf".into()
                 }
             ))
         );
@@ -285,10 +512,10 @@ mod test {
             Ok((
                 "",
                 FileSection {
-                    section: ".idata$5",
-                    address: "0x000000006711f38c",
-                    size: "0x4",
-                    file: "c:/toolbase/_ldata/mingw/comp_5.3.0_w64_2f/bin/../lib/gcc/x86_64-w64-mingw32/5.3.0/../../../../x86_64-w64-mingw32/lib/../lib32/libmsvcrt.a(dqgfs01158.o)"
+                    section: ".idata$5".into(),
+                    address: "0x000000006711f38c".into(),
+                    size: "0x4".into(),
+                    file: "c:/toolbase/_ldata/mingw/comp_5.3.0_w64_2f/bin/../lib/gcc/x86_64-w64-mingw32/5.3.0/../../../../x86_64-w64-mingw32/lib/../lib32/libmsvcrt.a(dqgfs01158.o)".into()
                 }
             ))
         );
@@ -298,12 +525,61 @@ mod test {
             Ok((
                 "",
                 FileSection {
-                    section: ".text",
-                    address: "0x00000000634519e0",
-                    size: "0xe0",
-                    file: "c:/toolbase/_ldata/mingw/comp_5.3.0_w64_2f/bin/../lib/gcc/x86_64-w64-mingw32/5.3.0/../../../../x86_64-w64-mingw32/lib/../lib32/libmingw32.a(lib32_libmingw32_a-atonexit.o)"
+                    section: ".text".into(),
+                    address: "0x00000000634519e0".into(),
+                    size: "0xe0".into(),
+                    file: "c:/toolbase/_ldata/mingw/comp_5.3.0_w64_2f/bin/../lib/gcc/x86_64-w64-mingw32/5.3.0/../../../../x86_64-w64-mingw32/lib/../lib32/libmingw32.a(lib32_libmingw32_a-atonexit.o)".into()
                 }
             ))
         );
     }
+
+    #[test]
+    fn test_generated_lines() {
+        use crate::generator::{generate, Options, PathStyle};
+
+        for paths in PathStyle::ALL {
+            for archives in [false, true] {
+                let options = Options {
+                    sections: 2,
+                    input_sections: 20,
+                    paths,
+                    archives,
+                    ..Default::default()
+                };
+                let map = generate(&options);
+                let mut rest = &map[map.find("\n.").unwrap() + 1..];
+                let (mut sections, mut files, mut fills, mut symbols) = (0, 0, 0, 0);
+                while !rest.starts_with("OUTPUT(") {
+                    rest = if let Ok((rest, _)) = section_declaration(rest) {
+                        sections += 1;
+                        rest
+                    } else if let Ok((rest, file_section)) = file_section(rest) {
+                        assert!(file_section.file.ends_with(".o)") == archives);
+                        assert_eq!(
+                            file_section.file.contains('\\'),
+                            paths == PathStyle::Windows
+                        );
+                        files += 1;
+                        rest
+                    } else if let Ok((rest, _)) = fill_line(rest) {
+                        fills += 1;
+                        rest
+                    } else if let Ok((rest, _)) = assignment_line(rest) {
+                        rest
+                    } else if let Ok((rest, _)) = symbol_line(rest) {
+                        symbols += 1;
+                        rest
+                    } else {
+                        alt((section_rule_line, empty_till_end_of_line))(rest)
+                            .unwrap_or_else(|_| panic!("unparsed line {rest:?}"))
+                            .0
+                    };
+                }
+                assert_eq!((sections, files, symbols), (2, 40, 80));
+                assert_eq!(fills, map.matches("*fill*").count());
+                assert_eq!(output_line(rest).unwrap().1.file, "generated.elf");
+            }
+        }
+    }
 }
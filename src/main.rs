@@ -1,34 +1,551 @@
-use clap::{Parser, ValueEnum};
-use mapper::{parse, to_csv, to_json};
-use std::{fs, path::PathBuf};
+use anyhow::{anyhow, bail, Context};
+use clap::{
+    builder::{PossibleValuesParser, TypedValueParser},
+    Parser, Subcommand, ValueEnum,
+};
+#[cfg(feature = "schema")]
+use mapper::schema;
+use mapper::{
+    exporters::{Bloaty, Csv, DataSource},
+    formats::{SectionGroups, SAMPLE_SIZE},
+    generator::{self, PathStyle},
+    open_map, read_map, reports, Exporters, MapFile, MapText, Registry, Table, Warning,
+};
+use std::{
+    ffi::OsStr,
+    fs::File,
+    io::{self, BufRead, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+};
 
+/// How the reports print their tables.
 #[derive(Debug, Clone, Copy, ValueEnum)]
-enum Format {
-    Csv,
-    Json,
+enum TableFormat {
+    Table,
+    Markdown,
+}
+
+impl TableFormat {
+    fn print(self, table: &Table) {
+        match self {
+            TableFormat::Table => print!("{}", table.to_text()),
+            TableFormat::Markdown => print!("{}", table.to_markdown()),
+        }
+    }
 }
 
 #[derive(Debug, Parser)]
-#[command(author, version, about)]
+#[command(
+    author,
+    version,
+    about,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 struct Args {
-    #[arg(short, long, value_enum, default_value = "csv", help = "output format")]
-    format: Format,
-    #[arg(help = "input map file")]
-    input: PathBuf,
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[arg(
+        short,
+        long,
+        default_value = "csv",
+        value_parser = exporter_names(),
+        help = "output format"
+    )]
+    format: String,
+    #[command(flatten)]
+    map: Option<MapArgs>,
     #[arg(
         default_value = "./output",
-        help = "output file name (extension will be added according to selected format)"
+        help = "output file name, the extension of the selected format is added if missing; `-` for standard output"
     )]
     output: PathBuf,
+    #[arg(
+        short = 'o',
+        long = "output",
+        value_name = "FILE",
+        conflicts_with = "output",
+        help = "file to write to, used as is; `-` for standard output"
+    )]
+    to: Option<PathBuf>,
+    #[arg(
+        short,
+        long,
+        value_delimiter = ',',
+        default_value = "sections",
+        value_parser = data_sources(),
+        help = "what the bloaty format breaks sizes down by, like bloaty's -d"
+    )]
+    data_sources: Vec<DataSource>,
+    #[arg(
+        long,
+        help = "write rows while the map file is read, for GNU ld maps too large for memory; csv format only, rows in map order"
+    )]
+    stream: bool,
+}
+
+#[derive(Debug, clap::Args)]
+struct MapArgs {
+    #[arg(
+        short,
+        long,
+        default_value = "auto",
+        value_parser = linker_names(),
+        help = "linker that produced the map file"
+    )]
+    linker: String,
+    #[arg(
+        required = true,
+        help = "input map file, may be compressed with gzip, xz or zstd; `-` for standard input"
+    )]
+    input: PathBuf,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    #[command(about = "Report which linker produced a map file")]
+    Detect {
+        #[arg(help = "input map file, `-` for standard input")]
+        input: PathBuf,
+    },
+    #[command(about = "List loaded inputs that contributed no bytes to the output")]
+    Unused {
+        #[command(flatten)]
+        map: MapArgs,
+    },
+    #[command(about = "Show how much of each memory region is used")]
+    Usage {
+        #[command(flatten)]
+        map: MapArgs,
+        #[arg(
+            short,
+            long,
+            value_enum,
+            default_value = "table",
+            help = "report format"
+        )]
+        format: TableFormat,
+    },
+    #[command(about = "Show padding the linker inserted for alignment")]
+    Padding {
+        #[command(flatten)]
+        map: MapArgs,
+        #[arg(long, default_value_t = 10, help = "number of largest gaps to list")]
+        top: usize,
+        #[arg(
+            short,
+            long,
+            value_enum,
+            default_value = "table",
+            help = "report format"
+        )]
+        format: TableFormat,
+    },
+    #[command(about = "Show what each linker script rule placed into the output")]
+    Rules {
+        #[command(flatten)]
+        map: MapArgs,
+        #[arg(long, help = "only list rules that matched nothing")]
+        unmatched: bool,
+        #[arg(
+            short,
+            long,
+            value_enum,
+            default_value = "table",
+            help = "report format"
+        )]
+        format: TableFormat,
+    },
+    #[cfg(feature = "schema")]
+    #[command(about = "Print the JSON Schema of the json output format")]
+    Schema,
+    #[command(about = "Write a synthetic GNU ld map file, e.g. for benchmarks")]
+    Gen {
+        #[arg(long, default_value_t = 8, help = "number of output sections")]
+        sections: usize,
+        #[arg(
+            long,
+            default_value_t = 100,
+            conflicts_with = "size",
+            help = "number of input sections in each output section"
+        )]
+        input_sections: usize,
+        #[arg(
+            long,
+            default_value_t = 2,
+            help = "number of symbols in each input section"
+        )]
+        symbols: usize,
+        #[arg(long, default_value_t = 64, help = "number of input files")]
+        files: usize,
+        #[arg(
+            long,
+            default_value = "unix",
+            value_parser = path_styles(),
+            help = "how input file paths are written"
+        )]
+        paths: PathStyle,
+        #[arg(long, help = "make the input files members of archives")]
+        archives: bool,
+        #[arg(
+            long,
+            default_value_t = 1,
+            help = "seed of section sizes and alignments"
+        )]
+        seed: u64,
+        #[arg(
+            long,
+            value_parser = parse_size,
+            help = "approximate size of the map file in bytes, with an optional K, M or G suffix; sets the number of input sections"
+        )]
+        size: Option<u64>,
+        #[arg(
+            short = 'o',
+            long = "output",
+            value_name = "FILE",
+            default_value = "-",
+            help = "file to write to; `-` for standard output"
+        )]
+        output: PathBuf,
+    },
+}
+
+fn linker_names() -> PossibleValuesParser {
+    let names = std::iter::once("auto").chain(Registry::default().names());
+    PossibleValuesParser::new(names)
+}
+
+fn exporter_names() -> PossibleValuesParser {
+    PossibleValuesParser::new(Exporters::default().names())
+}
+
+fn read(path: &Path) -> anyhow::Result<MapText> {
+    read_map(path).with_context(|| format!("cannot read {}", path.display()))
+}
+
+/// `name` with `.extension` added, unless it already ends with it: `build.v2` becomes
+/// `build.v2.csv`. `-` (standard output) is left as is.
+fn output_path(name: &Path, extension: &str) -> PathBuf {
+    if name == Path::new("-") || name.extension() == Some(OsStr::new(extension)) {
+        return name.to_path_buf();
+    }
+    let mut path = name.as_os_str().to_owned();
+    path.push(".");
+    path.push(extension);
+    path.into()
+}
+
+/// Prints the parts of the map file at `path` that were skipped to standard error.
+fn warn(path: &Path, warnings: &[Warning]) {
+    for warning in warnings {
+        eprintln!("warning: {}: {warning}", path.display());
+    }
+}
+
+fn data_sources() -> impl TypedValueParser<Value = DataSource> {
+    let names = DataSource::ALL.map(DataSource::name);
+    PossibleValuesParser::new(names).map(|name| name.parse::<DataSource>().unwrap())
+}
+
+fn path_styles() -> impl TypedValueParser<Value = PathStyle> {
+    let names = PathStyle::ALL.map(PathStyle::name);
+    PossibleValuesParser::new(names).map(|name| name.parse::<PathStyle>().unwrap())
+}
+
+/// `512`, `64K`, `100M` or `2G` as a number of bytes.
+fn parse_size(size: &str) -> Result<u64, String> {
+    let (number, unit) = match size.char_indices().last() {
+        Some((index, 'K' | 'k')) => (&size[..index], 1 << 10),
+        Some((index, 'M' | 'm')) => (&size[..index], 1 << 20),
+        Some((index, 'G' | 'g')) => (&size[..index], 1 << 30),
+        _ => (size, 1),
+    };
+    number
+        .parse::<u64>()
+        .ok()
+        .and_then(|number| number.checked_mul(unit))
+        .ok_or_else(|| format!("invalid size {size}"))
+}
+
+impl MapArgs {
+    fn read(&self) -> anyhow::Result<MapText> {
+        read(&self.input)
+    }
+
+    /// Opens the map to be read line by line. With `--linker auto` the start of the map is
+    /// checked to be from GNU ld, the only format that can be streamed.
+    fn open_gnu(&self, registry: &Registry) -> anyhow::Result<impl BufRead> {
+        let context = || format!("cannot read {}", self.input.display());
+        let mut reader = open_map(&self.input).with_context(context)?;
+        let mut sample = Vec::new();
+        reader
+            .by_ref()
+            .take(SAMPLE_SIZE as u64)
+            .read_to_end(&mut sample)
+            .with_context(context)?;
+        match self.linker.as_str() {
+            "gnu" => {}
+            "auto" => match registry.detect(&String::from_utf8_lossy(&sample)) {
+                Some(detection) if detection.format.name() != "gnu" => bail!(
+                    "--stream only reads GNU ld map files, {} is from {}",
+                    self.input.display(),
+                    detection.format.name()
+                ),
+                _ => {}
+            },
+            _ => bail!("--stream only reads GNU ld map files"),
+        }
+        Ok(io::Cursor::new(sample).chain(reader))
+    }
+
+    fn parse<'a>(&self, registry: &Registry, input: &'a str) -> anyhow::Result<MapFile<'a>> {
+        // an explicit --linker is used as is, without looking at the map
+        let format = match self.linker.as_str() {
+            "auto" => match registry.detect(input) {
+                Some(detection) => Some(detection.format),
+                None => registry.get("gnu"),
+            },
+            name => registry.get(name),
+        }
+        .expect("linker names are validated by clap");
+        let mut output = format.parse(input)?;
+        output.source = Some(self.input.display().to_string());
+        warn(&self.input, &output.warnings);
+        Ok(output)
+    }
+}
+
+/// Set once the reader of standard output went away, e.g. `head`. Exporters wrap write errors
+/// in their own error types, so the broken pipe is noted where it happens.
+static STDOUT_CLOSED: AtomicBool = AtomicBool::new(false);
+
+/// Buffered standard output, noting a broken pipe in [`STDOUT_CLOSED`].
+struct Stdout(BufWriter<io::StdoutLock<'static>>);
+
+impl Stdout {
+    fn new() -> Self {
+        Stdout(BufWriter::new(io::stdout().lock()))
+    }
+
+    fn note<T>(result: io::Result<T>) -> io::Result<T> {
+        if let Err(error) = &result {
+            if error.kind() == io::ErrorKind::BrokenPipe {
+                STDOUT_CLOSED.store(true, Ordering::Relaxed);
+            }
+        }
+        result
+    }
+}
+
+impl Write for Stdout {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        Stdout::note(self.0.write(buf))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Stdout::note(self.0.flush())
+    }
 }
 
 fn main() -> anyhow::Result<()> {
-    let args = Args::parse();
-    let input = fs::read_to_string(args.input)?;
-    let (_input, output) = parse(&input).unwrap();
-    match args.format {
-        Format::Csv => to_csv(&output, &args.output.with_extension("csv"))?,
-        Format::Json => to_json(&output, &args.output.with_extension("json"))?,
+    match run(Args::parse()) {
+        // a reader that stops early has all it wanted, like with other command line tools
+        Err(_) if STDOUT_CLOSED.load(Ordering::Relaxed) => Ok(()),
+        result => result,
+    }
+}
+
+fn run(args: Args) -> anyhow::Result<()> {
+    let registry = Registry::default();
+
+    match args.command {
+        Some(Command::Detect { input }) => {
+            let input = read(&input)?;
+            let detections = registry.detect_all(&input);
+            if detections.is_empty() {
+                println!("unknown");
+            }
+            for detection in detections {
+                println!(
+                    "{:<10} {:>5.1}%",
+                    detection.format.name(),
+                    detection.confidence * 100.0
+                );
+            }
+        }
+        Some(Command::Unused { map }) => {
+            let input = map.read()?;
+            let output = map.parse(&registry, &input)?;
+            for path in reports::unused_inputs(&output) {
+                println!("{path}");
+            }
+        }
+        Some(Command::Usage { map, format }) => {
+            let input = map.read()?;
+            let output = map.parse(&registry, &input)?;
+            let mut table = Table::new(["Region", "Used", "Size", "Used %"]);
+            for region in reports::region_usage(&output) {
+                let percent = region.used as f64 * 100.0 / region.length.max(1) as f64;
+                table.push([
+                    region.name.to_string(),
+                    region.used.to_string(),
+                    region.length.to_string(),
+                    format!("{percent:.2}%"),
+                ]);
+            }
+            format.print(&table);
+        }
+        Some(Command::Padding { map, top, format }) => {
+            let input = map.read()?;
+            let output = map.parse(&registry, &input)?;
+            let mut table = Table::new(["Section", "Padding", "Size"]);
+            for padding in reports::section_padding(&output) {
+                table.push([
+                    padding.section.to_string(),
+                    padding.bytes.to_string(),
+                    padding.size.to_string(),
+                ]);
+            }
+            format.print(&table);
+            println!();
+            let mut table = Table::new(["Section", "Address", "Size", "After"]);
+            for gap in reports::largest_gaps(&output, top) {
+                let after = gap
+                    .after
+                    .map_or(String::new(), |fs| format!("{} ({})", fs.file, fs.section));
+                table.push([
+                    gap.section.to_string(),
+                    format!("{:#x}", gap.address),
+                    gap.size.to_string(),
+                    after,
+                ]);
+            }
+            format.print(&table);
+        }
+        Some(Command::Rules {
+            map,
+            unmatched,
+            format,
+        }) => {
+            let input = map.read()?;
+            let output = map.parse(&registry, &input)?;
+            let rules = match unmatched {
+                true => reports::unmatched_rules(&output),
+                false => reports::rule_usage(&output),
+            };
+            let mut table = Table::new(["Section", "Inputs", "Bytes", "Rule"]);
+            for rule in rules {
+                table.push([
+                    rule.section.to_string(),
+                    rule.inputs.to_string(),
+                    rule.bytes.to_string(),
+                    rule.rule.to_string(),
+                ]);
+            }
+            format.print(&table);
+        }
+        #[cfg(feature = "schema")]
+        Some(Command::Schema) => {
+            println!("{}", serde_json::to_string_pretty(&schema())?);
+        }
+        Some(Command::Gen {
+            sections,
+            input_sections,
+            symbols,
+            files,
+            paths,
+            archives,
+            seed,
+            size,
+            output,
+        }) => {
+            let mut options = generator::Options {
+                sections,
+                input_sections,
+                symbols,
+                files,
+                paths,
+                archives,
+                seed,
+            };
+            if let Some(size) = size {
+                options = options.with_size(size);
+            }
+            let mut writer: Box<dyn Write> = match output == Path::new("-") {
+                true => Box::new(Stdout::new()),
+                false => Box::new(BufWriter::new(
+                    File::create(&output)
+                        .with_context(|| format!("cannot create {}", output.display()))?,
+                )),
+            };
+            generator::write_map(&options, &mut writer)?;
+            writer.flush()?;
+        }
+        None => {
+            let map = args.map.ok_or_else(|| anyhow!("missing input map file"))?;
+            let mut exporters = Exporters::default();
+            exporters.register(Bloaty {
+                sources: args.data_sources,
+            });
+            let exporter = exporters
+                .get(&args.format)
+                .expect("format names are validated by clap");
+            let path = match args.to {
+                Some(path) => path,
+                None => output_path(&args.output, exporter.extension()),
+            };
+            if args.stream {
+                if exporter.name() != "csv" {
+                    bail!("--stream only writes the csv format");
+                }
+                let mut groups = SectionGroups::new(map.open_gnu(&registry)?);
+                let mut writer: Box<dyn Write> = match path == Path::new("-") {
+                    true => Box::new(Stdout::new()),
+                    false => Box::new(BufWriter::new(File::create(&path)?)),
+                };
+                Csv.write_stream(&mut groups, &mut writer)?;
+                writer.flush()?;
+                warn(&map.input, groups.warnings());
+                if !groups.found_memory_map() {
+                    bail!("no memory map found in {}", map.input.display());
+                }
+                return Ok(());
+            }
+            let input = map.read()?;
+            let output = map.parse(&registry, &input)?;
+            if path == Path::new("-") {
+                let mut stdout = Stdout::new();
+                exporter.write(&output, &mut stdout)?;
+                stdout.flush()?;
+            } else {
+                exporter.export(&output, &path)?;
+            }
+        }
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_output_path() {
+        let path = |name: &str| output_path(Path::new(name), "csv");
+        assert_eq!(path("./output"), Path::new("./output.csv"));
+        assert_eq!(path("build.v2"), Path::new("build.v2.csv"));
+        assert_eq!(path("symbols.csv"), Path::new("symbols.csv"));
+        assert_eq!(path("-"), Path::new("-"));
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("512"), Ok(512));
+        assert_eq!(parse_size("64K"), Ok(64 << 10));
+        assert_eq!(parse_size("100M"), Ok(100 << 20));
+        assert_eq!(parse_size("2g"), Ok(2 << 30));
+        assert!(parse_size("M").is_err());
+        assert!(parse_size("1.5M").is_err());
+    }
+}
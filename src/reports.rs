@@ -0,0 +1,510 @@
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+
+use serde::Serialize;
+
+use crate::types::*;
+use crate::units::{archive_member, hex_value};
+
+/// How many bytes a loaded input contributed to the output.
+#[derive(Debug, PartialEq)]
+pub struct InputUsage<'a> {
+    pub path: &'a str,
+    pub bytes: u64,
+}
+
+/// Shared libraries are loaded to resolve symbols but never contribute sections.
+fn is_shared_library(path: &str) -> bool {
+    let name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+    name.split('.').skip(1).any(|ext| ext == "so")
+        || [".dll", ".dylib", ".tbd"]
+            .iter()
+            .any(|ext| name.ends_with(ext))
+}
+
+/// Bytes contributed by each loaded input, in the order the inputs were loaded. Inputs loaded
+/// several times are listed once.
+pub fn input_usage<'a>(map: &'a MapFile) -> Vec<InputUsage<'a>> {
+    // bytes by the file named on file sections and, for archive members, by the archive too,
+    // so each input is found whether it was loaded as a file or as an archive
+    let mut bytes: HashMap<&str, u64> = HashMap::new();
+    for fsg in map.sections.iter().flat_map(|g| &g.file_section_groups) {
+        let file = &*fsg.file_section.file;
+        let size = hex_value(&fsg.file_section.size).unwrap_or(0);
+        *bytes.entry(file).or_default() += size;
+        if let Some((archive, _)) = archive_member(file) {
+            *bytes.entry(archive).or_default() += size;
+        }
+    }
+
+    let mut seen = HashSet::new();
+    map.inputs
+        .iter()
+        .filter(|input| seen.insert(&*input.path))
+        .map(|input| InputUsage {
+            path: &input.path,
+            bytes: bytes.get(&*input.path).copied().unwrap_or(0),
+        })
+        .collect()
+}
+
+/// Loaded inputs that contributed zero bytes to the output, e.g. stale libraries on the link
+/// line. Shared libraries are not reported.
+pub fn unused_inputs<'a>(map: &'a MapFile) -> Vec<&'a str> {
+    input_usage(map)
+        .into_iter()
+        .filter(|u| u.bytes == 0 && !is_shared_library(u.path))
+        .map(|u| u.path)
+        .collect()
+}
+
+/// How much of a memory region the output sections take.
+#[derive(Debug, PartialEq)]
+pub struct RegionUsage<'a> {
+    pub name: &'a str,
+    pub used: u64,
+    pub length: u64,
+}
+
+/// Sections that are not loaded into target memory.
+pub(crate) fn is_non_alloc(name: &str) -> bool {
+    [
+        ".debug",
+        ".comment",
+        ".stab",
+        ".ARM.attributes",
+        ".gnu.attributes",
+        ".symtab",
+        ".strtab",
+        ".shstrtab",
+    ]
+    .iter()
+    .any(|prefix| name.starts_with(prefix))
+}
+
+/// Sections that are zeroed at startup and so have no initial values to load.
+pub(crate) fn is_zero_initialised(name: &str) -> bool {
+    [".bss", ".sbss", ".tbss", ".zbss", ".noinit"]
+        .iter()
+        .any(|prefix| name.starts_with(prefix))
+}
+
+/// The memory regions of `map` with their origin and length, without `*default*`.
+pub(crate) fn located_regions<'a>(map: &'a MapFile) -> Vec<(&'a MemoryRegion<'a>, u64, u64)> {
+    map.memory_regions
+        .iter()
+        .filter(|region| region.name != "*default*")
+        .filter_map(|region| {
+            Some((
+                region,
+                hex_value(&region.origin)?,
+                hex_value(&region.length)?,
+            ))
+        })
+        .collect()
+}
+
+/// Index of the region in `regions` that holds `address`.
+pub(crate) fn region_at(regions: &[(&MemoryRegion, u64, u64)], address: u64) -> Option<usize> {
+    regions.iter().position(|(_, origin, length)| {
+        (*origin..origin.saturating_add(*length)).contains(&address)
+    })
+}
+
+/// Bytes of each memory region taken by output sections, like ld's `--print-memory-usage`.
+///
+/// A section counts against the region holding its address and, when it has a load address
+/// in another region, against that region too: `.data` takes RAM at run time and flash for
+/// its initial values. The `*default*` region is left out.
+pub fn region_usage<'a>(map: &'a MapFile) -> Vec<RegionUsage<'a>> {
+    let regions = located_regions(map);
+    let region_of = |address: u64| region_at(&regions, address);
+
+    let mut used = vec![0; regions.len()];
+    for group in &map.sections {
+        let section = &group.section;
+        let (Some(address), Some(size)) = (hex_value(&section.address), hex_value(&section.size))
+        else {
+            continue;
+        };
+        if size == 0 || is_non_alloc(&section.name) {
+            continue;
+        }
+        let run = region_of(address);
+        if let Some(index) = run {
+            used[index] += size;
+        }
+        if let Some(lma) = section.lma.filter(|_| !is_zero_initialised(&section.name)) {
+            match region_of(lma) {
+                Some(index) if Some(index) != run => used[index] += size,
+                _ => {}
+            }
+        }
+    }
+
+    regions
+        .into_iter()
+        .zip(used)
+        .map(|((region, _, length), used)| RegionUsage {
+            name: &region.name,
+            used,
+            length,
+        })
+        .collect()
+}
+
+/// Padding inside one output section.
+#[derive(Debug, PartialEq)]
+pub struct SectionPadding<'a> {
+    pub section: &'a str,
+    pub size: u64,
+    pub bytes: u64,
+}
+
+/// Bytes of fill in each output section that has any, in map order.
+pub fn section_padding<'a>(map: &'a MapFile) -> Vec<SectionPadding<'a>> {
+    map.sections
+        .iter()
+        .map(|group| SectionPadding {
+            section: &group.section.name,
+            size: hex_value(&group.section.size).unwrap_or(0),
+            bytes: group
+                .fills
+                .iter()
+                .map(|fill| hex_value(&fill.size).unwrap_or(0))
+                .sum(),
+        })
+        .filter(|padding| padding.bytes > 0)
+        .collect()
+}
+
+/// One fill and the input the linker had to pad after.
+#[derive(Debug, PartialEq)]
+pub struct Gap<'a> {
+    pub section: &'a str,
+    pub address: u64,
+    pub size: u64,
+    pub after: Option<&'a FileSection<'a>>,
+}
+
+/// The `count` largest fills, largest first. The input a fill follows is the file section
+/// placed last before it.
+pub fn largest_gaps<'a>(map: &'a MapFile, count: usize) -> Vec<Gap<'a>> {
+    let mut gaps: Vec<Gap> = Vec::new();
+    for group in &map.sections {
+        for fill in &group.fills {
+            let (Some(address), Some(size)) = (hex_value(&fill.address), hex_value(&fill.size))
+            else {
+                continue;
+            };
+            let after = group
+                .file_section_groups
+                .iter()
+                .map(|fsg| &fsg.file_section)
+                .filter(|fs| hex_value(&fs.address).is_some_and(|start| start <= address))
+                .max_by_key(|fs| hex_value(&fs.address));
+            gaps.push(Gap {
+                section: &group.section.name,
+                address,
+                size,
+                after,
+            });
+        }
+    }
+    gaps.sort_by_key(|gap| Reverse(gap.size));
+    gaps.truncate(count);
+    gaps
+}
+
+/// How much an input section description placed into its output section.
+#[derive(Debug, PartialEq)]
+pub struct RuleUsage<'a> {
+    pub section: &'a str,
+    pub rule: &'a str,
+    pub inputs: usize,
+    pub bytes: u64,
+}
+
+/// Input sections and bytes placed by each rule, in script order, followed by the rules of
+/// output sections left out for being empty. A rule repeated in the same output section is
+/// listed once.
+pub fn rule_usage<'a>(map: &'a MapFile) -> Vec<RuleUsage<'a>> {
+    let mut usage: Vec<RuleUsage> = Vec::new();
+    for group in &map.sections {
+        for rule in &group.rules {
+            if usage
+                .iter()
+                .any(|u| u.section == group.section.name && u.rule == *rule)
+            {
+                continue;
+            }
+            let placed: Vec<&FileSection> = group
+                .file_section_groups
+                .iter()
+                .filter(|fsg| fsg.rule.as_ref() == Some(rule))
+                .map(|fsg| &fsg.file_section)
+                .collect();
+            usage.push(RuleUsage {
+                section: &group.section.name,
+                rule,
+                inputs: placed.len(),
+                bytes: placed
+                    .iter()
+                    .map(|fs| hex_value(&fs.size).unwrap_or(0))
+                    .sum(),
+            });
+        }
+    }
+    for section in &map.empty_sections {
+        for rule in &section.rules {
+            usage.push(RuleUsage {
+                section: &section.name,
+                rule,
+                inputs: 0,
+                bytes: 0,
+            });
+        }
+    }
+    usage
+}
+
+/// Rules that placed no input section at all, candidates for removal from the linker script.
+pub fn unmatched_rules<'a>(map: &'a MapFile) -> Vec<RuleUsage<'a>> {
+    rule_usage(map)
+        .into_iter()
+        .filter(|u| u.inputs == 0)
+        .collect()
+}
+
+/// A memory region, output section, input file or symbol of [`size_tree`], with the bytes it
+/// takes.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct SizeNode<'a> {
+    pub name: &'a str,
+    pub address: Option<u64>,
+    pub size: u64,
+    pub children: Vec<SizeNode<'a>>,
+}
+
+/// Name of the child holding the bytes of a node that its other children do not account for,
+/// e.g. alignment padding or code before the first symbol of an input section.
+pub const OTHER: &str = "(other)";
+
+/// Name of the node holding the output sections that are in no memory region.
+pub const NO_REGION: &str = "(no region)";
+
+impl<'a> SizeNode<'a> {
+    fn new(name: &'a str, address: Option<u64>, size: u64) -> Self {
+        Self {
+            name,
+            address,
+            size,
+            children: Vec::new(),
+        }
+    }
+
+    fn add_other(&mut self) {
+        let children: u64 = self.children.iter().map(|c| c.size).sum();
+        if !self.children.is_empty() && children < self.size {
+            let other = SizeNode::new(OTHER, None, self.size - children);
+            self.children.push(other);
+        }
+    }
+}
+
+/// The bytes of the output broken down into memory region, output section, input file and
+/// symbol, e.g. to draw a treemap.
+///
+/// Sections are put into the region holding their address. Input files are named as on their
+/// file sections and take the bytes of all their file sections in the output section. A
+/// symbol takes the bytes up to the next symbol or the end of its file section. Sections that
+/// are not loaded into target memory, like debug information, are left out.
+pub fn size_tree<'a>(map: &'a MapFile) -> SizeNode<'a> {
+    let name = map.output.as_ref().map_or("output", |output| &output.file);
+    let mut root = SizeNode::new(name, None, 0);
+    let regions = located_regions(map);
+    let mut region_nodes: HashMap<&str, usize> = HashMap::new();
+    for group in &map.sections {
+        let section = &group.section;
+        let size = hex_value(&section.size).unwrap_or(0);
+        if size == 0 || is_non_alloc(&section.name) {
+            continue;
+        }
+        let address = hex_value(&section.address);
+        let mut section_node = SizeNode::new(&section.name, address, size);
+        let mut file_nodes: HashMap<&str, usize> = HashMap::new();
+        for fsg in &group.file_section_groups {
+            let file_section = &fsg.file_section;
+            let file_size = hex_value(&file_section.size).unwrap_or(0);
+            if file_size == 0 {
+                continue;
+            }
+            let index = *file_nodes.entry(&file_section.file).or_insert_with(|| {
+                let start = hex_value(&file_section.address);
+                section_node
+                    .children
+                    .push(SizeNode::new(&file_section.file, start, 0));
+                section_node.children.len() - 1
+            });
+            let file_node = &mut section_node.children[index];
+            file_node.size += file_size;
+            file_node.children.extend(symbol_sizes(fsg));
+        }
+        for file_node in &mut section_node.children {
+            file_node.add_other();
+        }
+        section_node.add_other();
+
+        let region = region_name(&regions, section).unwrap_or(NO_REGION);
+        let index = *region_nodes.entry(region).or_insert_with(|| {
+            root.children.push(SizeNode::new(region, None, 0));
+            root.children.len() - 1
+        });
+        let region_node = &mut root.children[index];
+        region_node.size += size;
+        region_node.children.push(section_node);
+        root.size += size;
+    }
+    root
+}
+
+/// The memory region of `section`: the one the map names or else the one of `regions` holding
+/// its address, like in [`region_usage`].
+fn region_name<'a>(
+    regions: &[(&'a MemoryRegion<'a>, u64, u64)],
+    section: &'a Section,
+) -> Option<&'a str> {
+    if let Some(region) = &section.memory_region {
+        return Some(region);
+    }
+    let index = region_at(regions, hex_value(&section.address)?)?;
+    Some(&regions[index].0.name)
+}
+
+/// Symbols of a file section, each taking the bytes up to the next symbol.
+pub(crate) fn symbol_sizes<'a>(fsg: &'a FileSectionGroup) -> Vec<SizeNode<'a>> {
+    let start = hex_value(&fsg.file_section.address).unwrap_or(0);
+    let end = start.saturating_add(hex_value(&fsg.file_section.size).unwrap_or(0));
+    let mut symbols: Vec<(&str, u64)> = fsg
+        .symbols
+        .iter()
+        .filter_map(|symbol| Some((&*symbol.name, hex_value(&symbol.address)?)))
+        .filter(|(_, address)| (start..end).contains(address))
+        .collect();
+    symbols.sort_by_key(|(_, address)| *address);
+    let ends = symbols
+        .iter()
+        .skip(1)
+        .map(|(_, address)| *address)
+        .chain(std::iter::once(end));
+    symbols
+        .iter()
+        .zip(ends)
+        .map(|((name, address), end)| SizeNode::new(name, Some(*address), end - address))
+        .filter(|node| node.size > 0)
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn file_section_group<'a>(file: &'a str, size: &'a str) -> FileSectionGroup<'a> {
+        FileSectionGroup {
+            file_section: FileSection {
+                section: ".text".into(),
+                file: file.into(),
+                address: "0x1000".into(),
+                size: size.into(),
+            },
+            symbols: Vec::new(),
+            rule: None,
+        }
+    }
+
+    #[test]
+    fn test_unused_inputs() {
+        let map = MapFile {
+            sections: vec![SectionGroup {
+                section: Section {
+                    name: ".text".into(),
+                    address: "0x1000".into(),
+                    size: "0x30".into(),
+                    lma: None,
+                    memory_region: None,
+                },
+                file_section_groups: vec![
+                    file_section_group("main.o", "0x20"),
+                    file_section_group("libfoo.a(foo.o)", "0x10"),
+                    file_section_group("empty.o", "0x0"),
+                ],
+                fills: Vec::new(),
+                rules: Vec::new(),
+                assignments: Vec::new(),
+            }],
+            inputs: [
+                "main.o",
+                "libfoo.a",
+                "libfoo.a",
+                "empty.o",
+                "libstale.a",
+                "/lib/libc.so.6",
+            ]
+            .into_iter()
+            .map(|path| LoadedInput { path: path.into() })
+            .collect(),
+            ..Default::default()
+        };
+        assert_eq!(
+            input_usage(&map)[1],
+            InputUsage {
+                path: "libfoo.a",
+                bytes: 0x10
+            }
+        );
+        assert_eq!(unused_inputs(&map), ["empty.o", "libstale.a"]);
+    }
+
+    #[test]
+    fn test_is_shared_library() {
+        assert!(is_shared_library("/lib/x86_64-linux-gnu/libc.so.6"));
+        assert!(is_shared_library("/usr/lib/libgcc_s.so"));
+        assert!(is_shared_library(r"c:\windows\kernel32.dll"));
+        assert!(!is_shared_library("/usr/lib/libc_nonshared.a"));
+        assert!(!is_shared_library("./build.so/main.o"));
+    }
+
+    #[test]
+    fn test_input_usage() {
+        let map = MapFile {
+            sections: vec![SectionGroup {
+                section: Section {
+                    name: ".text".into(),
+                    address: "0x1000".into(),
+                    size: "0xc".into(),
+                    lma: None,
+                    memory_region: None,
+                },
+                file_section_groups: vec![
+                    file_section_group("./libcompute.a(main.o)", "0x8"),
+                    file_section_group("app.o", "0x4"),
+                ],
+                fills: Vec::new(),
+                rules: Vec::new(),
+                assignments: Vec::new(),
+            }],
+            inputs: [
+                "./libcompute.a",
+                "app.o",
+                "app.o.bak",
+                "./libcompute.a(main.o)",
+            ]
+            .into_iter()
+            .map(|path| LoadedInput { path: path.into() })
+            .collect(),
+            ..Default::default()
+        };
+        let bytes: Vec<u64> = input_usage(&map).iter().map(|u| u.bytes).collect();
+        assert_eq!(bytes, [0x8, 0x4, 0, 0x8]);
+    }
+}
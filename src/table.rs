@@ -0,0 +1,141 @@
+use std::fmt::Write;
+
+/// Rows of text printed as an aligned plain-text table or as a GitHub-flavoured Markdown table.
+///
+/// Columns whose cells are all numbers (decimal, `0x` hex or percentages) are right-aligned.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Table {
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new<I: IntoIterator<Item = S>, S: Into<String>>(headers: I) -> Self {
+        Self {
+            headers: headers.into_iter().map(Into::into).collect(),
+            rows: Vec::new(),
+        }
+    }
+
+    /// Adds a row. Missing cells are left empty and cells beyond the headers are dropped.
+    pub fn push<I: IntoIterator<Item = S>, S: Into<String>>(&mut self, row: I) {
+        let mut row: Vec<String> = row.into_iter().map(Into::into).collect();
+        row.resize(self.headers.len(), String::new());
+        self.rows.push(row);
+    }
+
+    fn right_aligned(&self, column: usize) -> bool {
+        let mut cells = self
+            .rows
+            .iter()
+            .map(|row| row[column].as_str())
+            .filter(|cell| !cell.is_empty())
+            .peekable();
+        cells.peek().is_some() && cells.all(is_number)
+    }
+
+    fn widths(&self) -> Vec<usize> {
+        (0..self.headers.len())
+            .map(|column| {
+                self.rows
+                    .iter()
+                    .map(|row| row[column].chars().count())
+                    .chain(std::iter::once(self.headers[column].chars().count()))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect()
+    }
+
+    /// The table with its columns padded to the same width, headers underlined.
+    pub fn to_text(&self) -> String {
+        let widths = self.widths();
+        let right: Vec<bool> = (0..self.headers.len())
+            .map(|column| self.right_aligned(column))
+            .collect();
+        let line = |cells: &[String]| {
+            let mut line = String::new();
+            for (column, cell) in cells.iter().enumerate() {
+                if column > 0 {
+                    line.push_str("  ");
+                }
+                let width = widths[column];
+                match right[column] {
+                    true => write!(line, "{cell:>width$}"),
+                    false => write!(line, "{cell:<width$}"),
+                }
+                .unwrap();
+            }
+            line.trim_end().to_string() + "\n"
+        };
+        let underline: Vec<String> = widths.iter().map(|width| "-".repeat(*width)).collect();
+        let mut text = line(&self.headers) + &line(&underline);
+        for row in &self.rows {
+            text += &line(row);
+        }
+        text
+    }
+
+    /// The table in GitHub-flavoured Markdown, with `|` in cells escaped.
+    pub fn to_markdown(&self) -> String {
+        let line = |cells: &mut dyn Iterator<Item = String>| {
+            let cells: Vec<String> = cells.collect();
+            format!("| {} |\n", cells.join(" | "))
+        };
+        let escape = |cell: &String| cell.replace('|', "\\|");
+        let mut text = line(&mut self.headers.iter().map(escape));
+        text += &line(&mut (0..self.headers.len()).map(
+            |column| match self.right_aligned(column) {
+                true => "---:".to_string(),
+                false => "---".to_string(),
+            },
+        ));
+        for row in &self.rows {
+            text += &line(&mut row.iter().map(escape));
+        }
+        text
+    }
+}
+
+fn is_number(cell: &str) -> bool {
+    let cell = cell.strip_suffix('%').unwrap_or(cell);
+    match cell.strip_prefix("0x") {
+        Some(digits) => u64::from_str_radix(digits, 16).is_ok(),
+        // `inf` and `nan` parse as floats but are names here
+        None => cell.starts_with(|c: char| c.is_ascii_digit()) && cell.parse::<f64>().is_ok(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn table() -> Table {
+        let mut table = Table::new(["Region", "Used", "Used %"]);
+        table.push(["FLASH", "200", "0.31%"]);
+        table.push(["RAM|2", "84", "1.03%"]);
+        table
+    }
+
+    #[test]
+    fn test_to_text() {
+        assert_eq!(
+            table().to_text(),
+            "Region  Used  Used %\n\
+             ------  ----  ------\n\
+             FLASH    200   0.31%\n\
+             RAM|2     84   1.03%\n"
+        );
+    }
+
+    #[test]
+    fn test_to_markdown() {
+        assert_eq!(
+            table().to_markdown(),
+            "| Region | Used | Used % |\n\
+             | --- | ---: | ---: |\n\
+             | FLASH | 200 | 0.31% |\n\
+             | RAM\\|2 | 84 | 1.03% |\n"
+        );
+    }
+}
@@ -1,40 +1,218 @@
+use std::borrow::Cow;
+use std::fmt;
+
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
 pub struct Symbol<'a> {
-    pub name: &'a str,
-    pub address: &'a str,
+    #[serde(borrow)]
+    pub name: Cow<'a, str>,
+    #[serde(borrow)]
+    pub address: Cow<'a, str>,
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
 pub struct Section<'a> {
-    pub name: &'a str,
-    pub address: &'a str,
-    pub size: &'a str,
+    #[serde(borrow)]
+    pub name: Cow<'a, str>,
+    #[serde(borrow)]
+    pub address: Cow<'a, str>,
+    #[serde(borrow)]
+    pub size: Cow<'a, str>,
+    /// Load address, when it differs from the (run time) address, e.g. for `.data` copied
+    /// from flash to RAM at startup.
+    #[serde(default, with = "hex")]
+    #[cfg_attr(feature = "schema", schemars(with = "Option<String>"))]
+    pub lma: Option<u64>,
+    /// Memory region the section was placed in, when the map names it.
+    pub memory_region: Option<Cow<'a, str>>,
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
 pub struct FileSection<'a> {
-    pub section: &'a str,
-    pub file: &'a str,
-    pub address: &'a str,
-    pub size: &'a str,
+    #[serde(borrow)]
+    pub section: Cow<'a, str>,
+    #[serde(borrow)]
+    pub file: Cow<'a, str>,
+    #[serde(borrow)]
+    pub address: Cow<'a, str>,
+    #[serde(borrow)]
+    pub size: Cow<'a, str>,
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
 pub struct FileSectionGroup<'a> {
     #[serde(borrow)]
     pub file_section: FileSection<'a>,
     #[serde(borrow)]
     pub symbols: Vec<Symbol<'a>>,
+    /// The input section description that placed the file section, e.g. `*(.text .text.*)`.
+    pub rule: Option<Cow<'a, str>>,
+}
+
+/// Padding the linker inserted between input sections, usually to satisfy alignment.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct Fill<'a> {
+    #[serde(borrow)]
+    pub address: Cow<'a, str>,
+    #[serde(borrow)]
+    pub size: Cow<'a, str>,
+    pub pattern: Option<Cow<'a, str>>,
+}
+
+/// A symbol assignment from the linker script, e.g. `_stack_top = ORIGIN (RAM) + LENGTH (RAM)`.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct Assignment<'a> {
+    #[serde(borrow)]
+    pub address: Cow<'a, str>,
+    #[serde(borrow)]
+    pub lhs: Cow<'a, str>,
+    #[serde(borrow)]
+    pub expression: Cow<'a, str>,
+}
+
+impl Assignment<'_> {
+    /// Assignments to `.` move the location counter rather than define a symbol.
+    pub fn defines_symbol(&self) -> bool {
+        self.lhs != "."
+    }
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
 pub struct SectionGroup<'a> {
     #[serde(borrow)]
     pub section: Section<'a>,
     #[serde(borrow)]
     pub file_section_groups: Vec<FileSectionGroup<'a>>,
+    #[serde(borrow, default)]
+    pub fills: Vec<Fill<'a>>,
+    /// Input section descriptions listed in the output section, in script order.
+    #[serde(default)]
+    pub rules: Vec<Cow<'a, str>>,
+    /// Assignments listed after the section declaration. ld.bfd prints assignments that
+    /// follow an output section in the script the same way as ones inside it, so those end
+    /// up here too.
+    #[serde(borrow, default)]
+    pub assignments: Vec<Assignment<'a>>,
+}
+
+/// `OUTPUT(firmware.elf elf32-littlearm)`: the linked file and its object file format.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct Output<'a> {
+    #[serde(borrow)]
+    pub file: Cow<'a, str>,
+    #[serde(borrow)]
+    pub format: Cow<'a, str>,
+}
+
+/// An output section the linker left out because nothing was placed into it.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct EmptySection<'a> {
+    #[serde(borrow)]
+    pub name: Cow<'a, str>,
+    pub rules: Vec<Cow<'a, str>>,
+}
+
+/// A region of the `MEMORY` command of the linker script.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct MemoryRegion<'a> {
+    #[serde(borrow)]
+    pub name: Cow<'a, str>,
+    #[serde(borrow)]
+    pub origin: Cow<'a, str>,
+    #[serde(borrow)]
+    pub length: Cow<'a, str>,
+    #[serde(borrow)]
+    pub attributes: Cow<'a, str>,
+}
+
+/// An input file named on the link line (a `LOAD` line in GNU ld maps).
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct LoadedInput<'a> {
+    #[serde(borrow)]
+    pub path: Cow<'a, str>,
+}
+
+/// A symbol of the cross reference table (`--cref`). When an input file defines the symbol it
+/// is listed first, followed by the files that reference the symbol.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct CrossReference<'a> {
+    #[serde(borrow)]
+    pub symbol: Cow<'a, str>,
+    pub files: Vec<Cow<'a, str>>,
+}
+
+/// Part of a map file the parser did not understand and skipped.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct Warning {
+    /// Line the skipped part starts on, counting from 1.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// Everything read from one map file.
+///
+/// Fields may be added in later versions; they are defaulted when missing from serialized
+/// input, so older exports keep deserializing.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(default)]
+pub struct MapFile<'a> {
+    /// Path of the map file, when it was read from one.
+    pub source: Option<String>,
+    /// Name of the format the map file was parsed as, e.g. `gnu`.
+    pub linker: Option<Cow<'a, str>>,
+    #[serde(borrow)]
+    pub output: Option<Output<'a>>,
+    #[serde(borrow)]
+    pub sections: Vec<SectionGroup<'a>>,
+    #[serde(borrow)]
+    pub inputs: Vec<LoadedInput<'a>>,
+    #[serde(borrow)]
+    pub memory_regions: Vec<MemoryRegion<'a>>,
+    #[serde(borrow)]
+    pub empty_sections: Vec<EmptySection<'a>>,
+    /// Input sections that were removed from the output, e.g. by `--gc-sections`.
+    #[serde(borrow)]
+    pub discarded: Vec<FileSection<'a>>,
+    /// Assignments made outside of any output section.
+    #[serde(borrow)]
+    pub assignments: Vec<Assignment<'a>>,
+    #[serde(borrow)]
+    pub cross_references: Vec<CrossReference<'a>>,
+    pub warnings: Vec<Warning>,
+}
+
+/// The JSON document written by `to_json`: the map file together with the version of the
+/// layout it was written in.
+#[derive(Serialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct Export<'a> {
+    /// `SCHEMA_VERSION` of the mapper that wrote the document.
+    pub schema_version: u32,
+    #[serde(flatten)]
+    pub map: &'a MapFile<'a>,
 }
 
 #[derive(Serialize)]
@@ -49,4 +227,236 @@ pub struct Record<'a> {
     pub old_section: &'a str,
     #[serde(borrow)]
     pub new_section: &'a str,
+    pub lma: Option<String>,
+    pub kind: SymbolKind,
+    pub rule: Option<&'a str>,
+}
+
+/// Where an exported symbol was defined.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SymbolKind {
+    /// Defined by an input file.
+    Object,
+    /// Assigned by the linker script.
+    LinkerScript,
+}
+
+impl SymbolKind {
+    /// The name the kind is serialized as.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SymbolKind::Object => "object",
+            SymbolKind::LinkerScript => "linker_script",
+        }
+    }
+}
+
+fn owned(text: Cow<'_, str>) -> Cow<'static, str> {
+    Cow::Owned(text.into_owned())
+}
+
+fn owned_all(texts: Vec<Cow<'_, str>>) -> Vec<Cow<'static, str>> {
+    texts.into_iter().map(owned).collect()
+}
+
+impl Symbol<'_> {
+    pub fn into_owned(self) -> Symbol<'static> {
+        Symbol {
+            name: owned(self.name),
+            address: owned(self.address),
+        }
+    }
+}
+
+impl Section<'_> {
+    pub fn into_owned(self) -> Section<'static> {
+        Section {
+            name: owned(self.name),
+            address: owned(self.address),
+            size: owned(self.size),
+            lma: self.lma,
+            memory_region: self.memory_region.map(owned),
+        }
+    }
+}
+
+impl FileSection<'_> {
+    pub fn into_owned(self) -> FileSection<'static> {
+        FileSection {
+            section: owned(self.section),
+            file: owned(self.file),
+            address: owned(self.address),
+            size: owned(self.size),
+        }
+    }
+}
+
+impl FileSectionGroup<'_> {
+    pub fn into_owned(self) -> FileSectionGroup<'static> {
+        FileSectionGroup {
+            file_section: self.file_section.into_owned(),
+            symbols: self.symbols.into_iter().map(Symbol::into_owned).collect(),
+            rule: self.rule.map(owned),
+        }
+    }
+}
+
+impl Fill<'_> {
+    pub fn into_owned(self) -> Fill<'static> {
+        Fill {
+            address: owned(self.address),
+            size: owned(self.size),
+            pattern: self.pattern.map(owned),
+        }
+    }
+}
+
+impl Assignment<'_> {
+    pub fn into_owned(self) -> Assignment<'static> {
+        Assignment {
+            address: owned(self.address),
+            lhs: owned(self.lhs),
+            expression: owned(self.expression),
+        }
+    }
+}
+
+impl SectionGroup<'_> {
+    pub fn into_owned(self) -> SectionGroup<'static> {
+        SectionGroup {
+            section: self.section.into_owned(),
+            file_section_groups: self
+                .file_section_groups
+                .into_iter()
+                .map(FileSectionGroup::into_owned)
+                .collect(),
+            fills: self.fills.into_iter().map(Fill::into_owned).collect(),
+            rules: owned_all(self.rules),
+            assignments: self
+                .assignments
+                .into_iter()
+                .map(Assignment::into_owned)
+                .collect(),
+        }
+    }
+}
+
+impl Output<'_> {
+    pub fn into_owned(self) -> Output<'static> {
+        Output {
+            file: owned(self.file),
+            format: owned(self.format),
+        }
+    }
+}
+
+impl EmptySection<'_> {
+    pub fn into_owned(self) -> EmptySection<'static> {
+        EmptySection {
+            name: owned(self.name),
+            rules: owned_all(self.rules),
+        }
+    }
+}
+
+impl MemoryRegion<'_> {
+    pub fn into_owned(self) -> MemoryRegion<'static> {
+        MemoryRegion {
+            name: owned(self.name),
+            origin: owned(self.origin),
+            length: owned(self.length),
+            attributes: owned(self.attributes),
+        }
+    }
+}
+
+impl LoadedInput<'_> {
+    pub fn into_owned(self) -> LoadedInput<'static> {
+        LoadedInput {
+            path: owned(self.path),
+        }
+    }
+}
+
+impl CrossReference<'_> {
+    pub fn into_owned(self) -> CrossReference<'static> {
+        CrossReference {
+            symbol: owned(self.symbol),
+            files: owned_all(self.files),
+        }
+    }
+}
+
+impl MapFile<'_> {
+    /// Copies everything still borrowed from the map file text, so the result can outlive it.
+    pub fn into_owned(self) -> MapFile<'static> {
+        MapFile {
+            source: self.source,
+            linker: self.linker.map(owned),
+            output: self.output.map(Output::into_owned),
+            sections: self
+                .sections
+                .into_iter()
+                .map(SectionGroup::into_owned)
+                .collect(),
+            inputs: self
+                .inputs
+                .into_iter()
+                .map(LoadedInput::into_owned)
+                .collect(),
+            memory_regions: self
+                .memory_regions
+                .into_iter()
+                .map(MemoryRegion::into_owned)
+                .collect(),
+            empty_sections: self
+                .empty_sections
+                .into_iter()
+                .map(EmptySection::into_owned)
+                .collect(),
+            discarded: self
+                .discarded
+                .into_iter()
+                .map(FileSection::into_owned)
+                .collect(),
+            assignments: self
+                .assignments
+                .into_iter()
+                .map(Assignment::into_owned)
+                .collect(),
+            cross_references: self
+                .cross_references
+                .into_iter()
+                .map(CrossReference::into_owned)
+                .collect(),
+            warnings: self.warnings,
+        }
+    }
+}
+
+/// (De)serializes optional addresses as `0x` prefixed hex strings, like the addresses that are
+/// kept as written in the map file.
+mod hex {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    use crate::units::hex_value;
+
+    pub fn serialize<S: Serializer>(value: &Option<u64>, serializer: S) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(value) => serializer.serialize_some(&format!("{value:#x}")),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<u64>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|value| {
+                hex_value(&value)
+                    .ok_or_else(|| D::Error::custom(format!("invalid address {value}")))
+            })
+            .transpose()
+    }
 }
@@ -19,16 +19,44 @@ pub fn hex_number(input: &str) -> IResult<&str, &str> {
     ))(input)
 }
 
+pub fn hex_digits(input: &str) -> IResult<&str, &str> {
+    nom::bytes::complete::take_while1(|c: char| c.is_ascii_hexdigit())(input)
+}
+
+/// Numeric value of a hex number as printed in a map file, with or without the `0x` prefix.
+pub fn hex_value(number: &str) -> Option<u64> {
+    let digits = number
+        .strip_prefix("0x")
+        .or_else(|| number.strip_prefix("0X"))
+        .unwrap_or(number);
+    u64::from_str_radix(digits, 16).ok()
+}
+
+/// Splits `lib.a(member.o)`, as input files are named, into the archive and the member.
+pub fn archive_member(path: &str) -> Option<(&str, &str)> {
+    let (archive, member) = path.strip_suffix(')')?.rsplit_once('(')?;
+    (!archive.is_empty()).then_some((archive, member))
+}
+
 pub fn identifier(input: &str) -> IResult<&str, &str> {
     recognize(many1(alt((tag("_"), tag("."), alphanumeric1))))(input)
 }
 
 pub fn symbol(input: &str) -> IResult<&str, &str> {
-    recognize(many1(alt((tag("_"), tag("@"), alphanumeric1))))(input)
+    recognize(pair(
+        alt((tag("_"), tag("@"), alphanumeric1)),
+        many0(alt((tag("_"), tag("@"), tag("."), alphanumeric1))),
+    ))(input)
 }
 
 pub fn section_name(input: &str) -> IResult<&str, &str> {
-    recognize(many1(alt((tag("."), tag("$"), tag("_"), alphanumeric1))))(input)
+    recognize(many1(alt((
+        tag("."),
+        tag("$"),
+        tag("_"),
+        tag("-"),
+        alphanumeric1,
+    ))))(input)
 }
 
 pub fn section_rule(input: &str) -> IResult<&str, &str> {
@@ -61,8 +89,17 @@ pub fn directory(input: &str) -> IResult<&str, &str> {
     recognize(pair(path_name, path_delimiter))(input)
 }
 
+/// A file or directory name. Any non-ASCII character is accepted, including the `U+FFFD` that
+/// replaces Latin-1 bytes in maps that are not UTF-8.
 pub fn path_name(input: &str) -> IResult<&str, &str> {
-    recognize(many1(alt((alphanumeric1, tag("."), tag("-"), tag("_")))))(input)
+    recognize(many1(alt((
+        alphanumeric1,
+        tag("."),
+        tag("-"),
+        tag("_"),
+        tag("+"),
+        recognize(satisfy(|c| !c.is_ascii())),
+    ))))(input)
 }
 
 pub fn file_name(input: &str) -> IResult<&str, &str> {
@@ -79,14 +116,46 @@ pub fn path(input: &str) -> IResult<&str, &str> {
     )))(input)
 }
 
-pub fn assignment(input: &str) -> IResult<&str, &str> {
-    recognize(tuple((identifier, space0, tag("="), not_line_ending)))(input)
+/// `__bss_start = .`, returning the symbol and the expression assigned to it.
+pub fn assignment(input: &str) -> IResult<&str, (&str, &str)> {
+    let (input, (lhs, _, _, _, expression)) =
+        tuple((identifier, space0, tag("="), space0, not_line_ending))(input)?;
+    Ok((input, (lhs, expression.trim_end())))
+}
+
+/// `PROVIDE (etext = .)`, also `PROVIDE_HIDDEN` and `HIDDEN`.
+pub fn provide(input: &str) -> IResult<&str, (&str, &str)> {
+    let (input, (_, _, _, (lhs, expression))) = tuple((
+        alt((tag("PROVIDE_HIDDEN"), tag("PROVIDE"), tag("HIDDEN"))),
+        space0,
+        tag("("),
+        assignment,
+    ))(input)?;
+    let expression = expression.strip_suffix(')').unwrap_or(expression);
+    Ok((input, (lhs, expression.trim_end())))
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
 
+    #[test]
+    fn test_hex_value() {
+        assert_eq!(hex_value("0x00000000632c1000"), Some(0x632c1000));
+        assert_eq!(hex_value("000002a8"), Some(0x2a8));
+        assert_eq!(hex_value("0x"), None);
+    }
+
+    #[test]
+    fn test_archive_member() {
+        assert_eq!(
+            archive_member("./libcompute.a(main.o)"),
+            Some(("./libcompute.a", "main.o"))
+        );
+        assert_eq!(archive_member("app.o"), None);
+        assert_eq!(archive_member("(other)"), None);
+    }
+
     #[test]
     fn test_identifier() {
         let input = "__image_base__";
@@ -94,12 +163,26 @@ mod test {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_symbol() {
+        assert_eq!(symbol("_imp__StackWalk@36"), Ok(("", "_imp__StackWalk@36")));
+        assert_eq!(
+            symbol("printf@@GLIBC_2.2.5"),
+            Ok(("", "printf@@GLIBC_2.2.5"))
+        );
+        assert!(symbol(". = ALIGN (0x8)").is_err());
+    }
+
     #[test]
     fn test_section_name() {
         assert!(section_name(".text").is_ok());
         assert!(section_name(".text.bsw").is_ok());
         assert!(section_name(".data.a4").is_ok());
         assert!(section_name(".bss.a4..someVAR").is_ok());
+        assert_eq!(
+            section_name(".note.gnu.build-id"),
+            Ok(("", ".note.gnu.build-id"))
+        );
     }
 
     #[test]
@@ -112,8 +195,29 @@ mod test {
     #[test]
     fn test_assignment() {
         let input = "__image_base__ = 0x632c0000";
-        let result = assignment(input);
-        assert!(result.is_ok());
+        assert_eq!(
+            assignment(input),
+            Ok(("", ("__image_base__", "0x632c0000")))
+        );
+    }
+
+    #[test]
+    fn test_provide() {
+        assert_eq!(provide("PROVIDE (etext = .)"), Ok(("", ("etext", "."))));
+        assert_eq!(
+            provide("PROVIDE_HIDDEN (__init_array_start = .)"),
+            Ok(("", ("__init_array_start", ".")))
+        );
+        assert_eq!(
+            provide("PROVIDE (__executable_start = SEGMENT_START (\"text-segment\", 0x0))"),
+            Ok((
+                "",
+                (
+                    "__executable_start",
+                    "SEGMENT_START (\"text-segment\", 0x0)"
+                )
+            ))
+        );
     }
 
     #[test]
@@ -127,5 +231,14 @@ mod test {
         assert!(result.is_ok());
 
         assert!(path("c:/toolbase/_ldata/mingw/comp_5.3.0_w64_2f/bin/../lib/gcc/x86_64-w64-mingw32/5.3.0/../../../../x86_64-w64-mingw32/lib/../lib32/libmingw32.a(lib32_libmingw32_a-atonexit.o)").is_ok());
+
+        assert_eq!(
+            path("/usr/lib/libstdc++.a(vterminate.o)"),
+            Ok(("", "/usr/lib/libstdc++.a(vterminate.o)"))
+        );
+        assert_eq!(
+            path("C:\\Users\\J\u{fc}rgen\\lib\u{fffd}.a(m\u{e4}in.o) "),
+            Ok((" ", "C:\\Users\\J\u{fc}rgen\\lib\u{fffd}.a(m\u{e4}in.o)"))
+        );
     }
 }
@@ -0,0 +1,41 @@
+use mapper::exporters::{Bloaty, DataSource};
+use mapper::{Exporter, MapFile};
+
+fn bloaty(sources: &[DataSource]) -> String {
+    let map = MapFile::parse(include_str!("fixtures/firmware.bfd.map")).unwrap();
+    let mut csv = Vec::new();
+    let bloaty = Bloaty {
+        sources: sources.to_vec(),
+    };
+    bloaty.write(&map, &mut csv).unwrap();
+    String::from_utf8(csv).unwrap()
+}
+
+#[test]
+fn sections() {
+    assert_eq!(
+        bloaty(&[DataSource::Sections]),
+        "sections,vmsize,filesize\n\
+         .text,168,168\n\
+         .bss,72,0\n\
+         .isr_vector,32,32\n\
+         .data,12,12\n"
+    );
+}
+
+#[test]
+fn sections_and_symbols() {
+    let csv = bloaty(&[DataSource::Sections, DataSource::Symbols]);
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines[0], "sections,symbols,vmsize,filesize");
+    assert_eq!(lines[1], ".text,reset_handler,99,99");
+    assert!(lines.contains(&".text,[section .text],31,31"));
+    assert!(lines.contains(&".bss,checksum,8,0"));
+}
+
+#[test]
+fn compile_units() {
+    let csv = bloaty(&["compileunits".parse().unwrap()]);
+    assert!(csv.contains("\nlibutil.a(util.o),"));
+    assert!("inputfiles".parse::<DataSource>().is_err());
+}
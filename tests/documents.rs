@@ -0,0 +1,28 @@
+use mapper::exporters::{Toml, Yaml};
+use mapper::{Exporter, MapFile, SCHEMA_VERSION};
+use serde::Deserialize;
+
+fn export(exporter: &dyn Exporter, map: &MapFile) -> String {
+    let mut document = Vec::new();
+    exporter.write(map, &mut document).unwrap();
+    String::from_utf8(document).unwrap()
+}
+
+#[test]
+fn yaml() {
+    let map = MapFile::parse(include_str!("fixtures/firmware.bfd.map")).unwrap();
+    let yaml = export(&Yaml, &map);
+    assert!(yaml.starts_with(&format!("schema_version: {SCHEMA_VERSION}\n")));
+    let loaded: MapFile = serde_yaml::from_str(&yaml).unwrap();
+    assert_eq!(loaded, map);
+}
+
+#[test]
+fn toml() {
+    let map = MapFile::parse(include_str!("fixtures/firmware.bfd.map")).unwrap();
+    let toml = export(&Toml, &map);
+    assert!(toml.starts_with(&format!("schema_version = {SCHEMA_VERSION}\n")));
+    // TOML has no null, so unset fields are left out and read back as unset
+    let loaded = MapFile::deserialize(toml::Deserializer::new(&toml)).unwrap();
+    assert_eq!(loaded, map);
+}
@@ -0,0 +1,22 @@
+use mapper::exporters::Folded;
+use mapper::{reports, Exporter, MapFile};
+
+#[test]
+fn folded() {
+    let map = MapFile::parse(include_str!("fixtures/firmware.bfd.map")).unwrap();
+    let mut folded = Vec::new();
+    Folded.write(&map, &mut folded).unwrap();
+    let folded = String::from_utf8(folded).unwrap();
+    let lines: Vec<&str> = folded.lines().collect();
+
+    assert_eq!(lines[0], "FLASH;.isr_vector;startup.o;vectors 32");
+    assert!(lines.contains(&"FLASH;.text;libutil.a;util.o;lut 5"));
+    assert!(lines.contains(&"FLASH;.text;(other) 31"));
+
+    // every byte is counted once
+    let total: u64 = lines
+        .iter()
+        .map(|line| line.rsplit_once(' ').unwrap().1.parse::<u64>().unwrap())
+        .sum();
+    assert_eq!(total, reports::size_tree(&map).size);
+}
@@ -0,0 +1,169 @@
+use mapper::{MapFile, Registry, SectionGroup};
+
+fn symbols<'m>(groups: &'m [SectionGroup]) -> Vec<(&'m str, &'m str, &'m str)> {
+    groups
+        .iter()
+        .flat_map(|g| &g.file_section_groups)
+        .flat_map(|fsg| {
+            fsg.symbols.iter().map(|s| {
+                (
+                    &*s.name,
+                    &*fsg.file_section.file,
+                    &*fsg.file_section.section,
+                )
+            })
+        })
+        .collect()
+}
+
+/// The symbols of `hello` defined in app.o and libcompute.a, the same whichever linker
+/// produced the map.
+fn assert_hello(input: &str) {
+    let map = MapFile::parse(input).unwrap();
+    let groups = &map.sections;
+    let sections: Vec<&str> = groups.iter().map(|g| &*g.section.name).collect();
+    for section in [".text", ".rodata", ".data", ".bss"] {
+        assert!(sections.contains(&section), "missing {section}");
+    }
+    let symbols = symbols(groups);
+    for expected in [
+        ("main", "app.o", ".text"),
+        ("compute", "./libcompute.a(main.o)", ".text"),
+        ("banner", "app.o", ".rodata"),
+        ("table", "app.o", ".data"),
+        ("counter", "./libcompute.a(main.o)", ".bss"),
+    ] {
+        assert!(symbols.contains(&expected), "missing {expected:?}");
+    }
+}
+
+#[test]
+fn bfd() {
+    assert_hello(include_str!("fixtures/hello.bfd.map"));
+}
+
+#[test]
+fn gold() {
+    assert_hello(include_str!("fixtures/hello.gold.map"));
+}
+
+#[test]
+fn mold() {
+    assert_hello(include_str!("fixtures/hello.mold.map"));
+}
+
+#[test]
+fn ld64() {
+    let input = include_str!("fixtures/hello.ld64.map");
+    let map = MapFile::parse(input).unwrap();
+    let groups = &map.sections;
+    let sections: Vec<&str> = groups.iter().map(|g| &*g.section.name).collect();
+    assert_eq!(
+        sections,
+        [
+            "__text",
+            "__stubs",
+            "__cstring",
+            "__unwind_info",
+            "__got",
+            "__data",
+            "__common"
+        ]
+    );
+    let symbols = symbols(groups);
+    assert!(symbols.contains(&("_main", "/Users/dev/build/app.o", "__text")));
+    assert!(symbols.contains(&(
+        "_compute",
+        "/Users/dev/build/libcompute.a(main.o)",
+        "__text"
+    )));
+    assert!(symbols.contains(&(
+        "literal string: hello",
+        "/Users/dev/build/app.o",
+        "__cstring"
+    )));
+    assert!(symbols.contains(&(
+        "_counter",
+        "/Users/dev/build/libcompute.a(main.o)",
+        "__common"
+    )));
+    assert!(!symbols.iter().any(|s| s.0 == "_unused"));
+}
+
+#[test]
+fn assignments() {
+    let map = MapFile::parse(include_str!("fixtures/firmware.bfd.map")).unwrap();
+    let stack_top = &map.assignments[0];
+    assert_eq!(
+        (&*stack_top.address, &*stack_top.lhs, &*stack_top.expression),
+        (
+            "0x0000000020002000",
+            "_stack_top",
+            "(ORIGIN (RAM) + LENGTH (RAM))"
+        )
+    );
+
+    let data = map.sections.iter().find(|g| g.section.name == ".data");
+    let symbols: Vec<&str> = data
+        .unwrap()
+        .assignments
+        .iter()
+        .filter(|a| a.defines_symbol())
+        .map(|a| &*a.lhs)
+        .collect();
+    assert_eq!(symbols, ["_sdata", "_edata"]);
+}
+
+#[test]
+fn metadata() {
+    let map = MapFile::parse(include_str!("fixtures/hello.bfd.map")).unwrap();
+    assert_eq!(map.linker.as_deref(), Some("gnu"));
+    let output = map.output.unwrap();
+    assert_eq!(
+        (&*output.file, &*output.format),
+        ("hello_bfd", "elf64-x86-64")
+    );
+
+    let map = MapFile::parse(include_str!("fixtures/hello.mold.map")).unwrap();
+    assert_eq!(map.linker.as_deref(), Some("mold"));
+    assert_eq!(map.output, None);
+}
+
+#[test]
+fn discarded_and_cross_references() {
+    let map = MapFile::parse(include_str!("fixtures/firmware.bfd.map")).unwrap();
+    assert_eq!(map.discarded.len(), 6);
+    assert_eq!(
+        (&*map.discarded[0].section, &*map.discarded[0].file),
+        (".comment", "startup.o")
+    );
+    let main = map.cross_references.iter().find(|r| r.symbol == "main");
+    assert_eq!(main.unwrap().files, ["main.o", "startup.o"]);
+    assert!(map.warnings.is_empty());
+
+    let map = MapFile::parse(include_str!("fixtures/hello.gold.map")).unwrap();
+    assert_eq!(map.discarded.len(), 7);
+}
+
+#[test]
+fn errors_and_warnings() {
+    let registry = Registry::default();
+    let tasking = registry.get("tasking").unwrap();
+    let error = tasking.parse("TASKING\nno tables here\n").unwrap_err();
+    assert_eq!((error.format, error.line), ("tasking", 1));
+
+    let input = "Linker script and memory map\n\n.text           0x0000000000001000        0x4\n?? garbage\n";
+    let map = MapFile::parse(input).unwrap();
+    assert_eq!(map.sections.len(), 1);
+    assert_eq!(map.warnings[0].line, 4);
+}
+
+#[test]
+fn not_a_map() {
+    for input in ["", "hello world", "random\ntext\nLOAD foo.o\n"] {
+        let error = MapFile::parse(input).unwrap_err();
+        assert_eq!((error.format, error.line), ("gnu", 1), "{input:?}");
+    }
+    // a header alone is an empty map rather than garbage
+    assert!(MapFile::parse("Linker script and memory map\n").is_ok());
+}
@@ -0,0 +1,65 @@
+use std::io::Cursor;
+
+use mapper::formats::SectionGroups;
+use mapper::generator::{generate, Options, PathStyle};
+use mapper::MapFile;
+
+fn options() -> impl Iterator<Item = Options> {
+    PathStyle::ALL.into_iter().flat_map(|paths| {
+        [false, true].map(|archives| Options {
+            sections: 6,
+            input_sections: 20,
+            symbols: 3,
+            files: 10,
+            paths,
+            archives,
+            ..Default::default()
+        })
+    })
+}
+
+#[test]
+fn parses() {
+    for options in options() {
+        let input = generate(&options);
+        let map = MapFile::parse(&input).unwrap();
+        assert_eq!(map.warnings, [], "{options:?}");
+        assert_eq!(map.linker.as_deref(), Some("gnu"));
+        assert_eq!(map.memory_regions.len(), 3);
+        assert_eq!(map.inputs.len(), 10);
+        assert_eq!(map.sections.len(), 6);
+        for group in &map.sections {
+            assert_eq!(group.file_section_groups.len(), 20);
+            assert_eq!(group.assignments.len(), 2);
+            for file_section in &group.file_section_groups {
+                assert_eq!(file_section.symbols.len(), 3);
+                let file = &file_section.file_section.file;
+                assert!(map.inputs.iter().any(|input| input.path == *file), "{file}");
+            }
+        }
+        assert_eq!(map.output.unwrap().file, "generated.elf");
+    }
+}
+
+#[test]
+fn deterministic() {
+    let options = Options::default();
+    assert_eq!(generate(&options), generate(&options));
+    let reseeded = Options {
+        seed: 2,
+        ..Options::default()
+    };
+    assert_ne!(generate(&options), generate(&reseeded));
+}
+
+#[test]
+fn streams() {
+    for options in options() {
+        let input = generate(&options);
+        let map = MapFile::parse(&input).unwrap();
+        let mut groups = SectionGroups::new(Cursor::new(input.as_bytes()));
+        let sections: Vec<_> = groups.by_ref().map(Result::unwrap).collect();
+        assert_eq!(sections, map.sections);
+        assert_eq!(groups.warnings(), []);
+    }
+}
@@ -0,0 +1,22 @@
+use std::fs;
+
+use mapper::exporters::Html;
+use mapper::{Exporter, MapFile};
+
+#[test]
+fn html() {
+    let mut map = MapFile::parse(include_str!("fixtures/firmware.bfd.map")).unwrap();
+    map.source = Some("</script>.map".to_string());
+    let path = std::env::temp_dir().join(format!("mapper-{}.html", std::process::id()));
+    Html.export(&map, &path).unwrap();
+    let html = fs::read_to_string(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert!(html.contains("<title>&lt;/script&gt;.map</title>"));
+    assert!(html.contains(r#""name":"reset_handler""#));
+    // the title in the data does not end the data block
+    assert!(html.contains(r#""title":"\u003c/script>.map""#));
+    assert_eq!(html.matches("</script>").count(), 2);
+    // nothing is loaded from the network
+    assert!(!html.contains("http://") && !html.contains("https://"));
+}
@@ -0,0 +1,97 @@
+use std::borrow::Cow;
+use std::fs;
+
+use mapper::{from_json, to_json, MapFile, SCHEMA_VERSION};
+
+#[test]
+fn round_trip() {
+    let input = include_str!("fixtures/firmware.bfd.map").replace("main.o", r"c:\build\main.o");
+    let mut map = MapFile::parse(&input).unwrap();
+    map.source = Some(r"c:\build\firmware.map".to_string());
+
+    let path = std::env::temp_dir().join(format!("mapper-round-trip-{}.json", std::process::id()));
+    to_json(&map, &path).unwrap();
+    let loaded = from_json(&path);
+    fs::remove_file(&path).unwrap();
+    let loaded = loaded.unwrap();
+
+    assert_eq!(loaded, map);
+    let main = loaded
+        .sections
+        .iter()
+        .flat_map(|g| &g.file_section_groups)
+        .find(|fsg| fsg.file_section.file.ends_with("main.o"))
+        .unwrap();
+    assert_eq!(main.file_section.file, r"c:\build\main.o");
+}
+
+#[test]
+fn owned() {
+    let map = {
+        let input = include_str!("fixtures/hello.bfd.map").to_string();
+        MapFile::parse(&input).unwrap().into_owned()
+    };
+    assert!(map
+        .sections
+        .iter()
+        .all(|g| matches!(g.section.name, Cow::Owned(_))));
+
+    // an owned map can be handed to another thread
+    let sections = std::thread::spawn(move || map.sections.len())
+        .join()
+        .unwrap();
+    assert!(sections > 0);
+}
+
+/// `schema.json` is the published schema of the JSON export. When this fails, regenerate it with
+/// `mapper schema > schema.json` and increase `SCHEMA_VERSION` if the change can break readers.
+#[cfg(feature = "schema")]
+#[test]
+fn schema() {
+    let schema = serde_json::to_value(mapper::schema()).unwrap();
+    let published: serde_json::Value =
+        serde_json::from_str(include_str!("../schema.json")).unwrap();
+    assert_eq!(schema, published);
+}
+
+#[test]
+fn schema_version() {
+    let map = MapFile::parse(include_str!("fixtures/hello.bfd.map")).unwrap();
+    let path = std::env::temp_dir().join(format!("mapper-version-{}.json", std::process::id()));
+    to_json(&map, &path).unwrap();
+    let mut document: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(document["schema_version"], SCHEMA_VERSION);
+
+    document["schema_version"] = (SCHEMA_VERSION + 1).into();
+    fs::write(&path, document.to_string()).unwrap();
+    let loaded = from_json(&path);
+    fs::remove_file(&path).unwrap();
+    assert!(loaded.is_err());
+}
+
+#[test]
+fn missing_fields() {
+    let map = MapFile::parse(include_str!("fixtures/firmware.bfd.map")).unwrap();
+    let path = std::env::temp_dir().join(format!("mapper-missing-{}.json", std::process::id()));
+    to_json(&map, &path).unwrap();
+    let mut document: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    for group in document["sections"].as_array_mut().unwrap() {
+        let group = group.as_object_mut().unwrap();
+        for field in ["fills", "rules", "assignments"] {
+            group.remove(field);
+        }
+        group["section"].as_object_mut().unwrap().remove("lma");
+    }
+    fs::write(&path, document.to_string()).unwrap();
+    let loaded = from_json(&path);
+    fs::remove_file(&path).unwrap();
+    let loaded = loaded.unwrap();
+
+    assert_eq!(loaded.sections.len(), map.sections.len());
+    assert!(loaded
+        .sections
+        .iter()
+        .all(|g| g.fills.is_empty() && g.rules.is_empty() && g.section.lma.is_none()));
+}
@@ -0,0 +1,16 @@
+use mapper::exporters::Puncover;
+use mapper::{Exporter, MapFile};
+
+#[test]
+fn puncover() {
+    let map = MapFile::parse(include_str!("fixtures/firmware.bfd.map")).unwrap();
+    let mut csv = Vec::new();
+    Puncover.write(&map, &mut csv).unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines[0], "file,symbol,type,code,static");
+    assert_eq!(lines[1], "startup.o,reset_handler,function,99,0");
+    assert!(lines.contains(&"main.o,buffer,variable,0,64"));
+    assert!(lines.contains(&"libutil.a(util.o),unused_helper,function,4,0"));
+    assert_eq!(lines.len(), 11);
+}
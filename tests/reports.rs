@@ -0,0 +1,114 @@
+use mapper::{reports, MapFile};
+
+#[test]
+fn unused_inputs() {
+    let map = MapFile::parse(include_str!("fixtures/hello.bfd.map")).unwrap();
+    assert_eq!(map.inputs.len(), 19);
+    assert_eq!(
+        reports::unused_inputs(&map),
+        [
+            "/usr/lib/gcc/x86_64-linux-gnu/12/libgcc.a",
+            "/usr/lib/x86_64-linux-gnu/libc_nonshared.a"
+        ]
+    );
+
+    let map = MapFile::parse(include_str!("fixtures/firmware.bfd.map")).unwrap();
+    assert_eq!(map.inputs.len(), 4);
+    assert_eq!(map.inputs[3].path, "libutil.a");
+    assert!(reports::unused_inputs(&map).is_empty());
+
+    let map = MapFile::parse(include_str!("fixtures/hello.ld64.map")).unwrap();
+    assert_eq!(map.inputs.len(), 3);
+    assert!(reports::unused_inputs(&map).is_empty());
+}
+
+#[test]
+fn region_usage() {
+    let map = MapFile::parse(include_str!("fixtures/firmware.bfd.map")).unwrap();
+    let data = map
+        .sections
+        .iter()
+        .find(|g| g.section.name == ".data")
+        .unwrap();
+    assert_eq!(data.section.lma, Some(0x80000c8));
+
+    let usage = reports::region_usage(&map);
+    let used: Vec<(&str, u64, u64)> = usage.iter().map(|u| (u.name, u.used, u.length)).collect();
+    // FLASH: .isr_vector + .text + initial values of .data; RAM: .data + .bss
+    assert_eq!(
+        used,
+        [
+            ("FLASH", 0x20 + 0xa8 + 0xc, 0x10000),
+            ("RAM", 0xc + 0x48, 0x2000)
+        ]
+    );
+}
+
+#[test]
+fn padding() {
+    let map = MapFile::parse(include_str!("fixtures/firmware.bfd.map")).unwrap();
+    let padding = reports::section_padding(&map);
+    assert_eq!(padding.len(), 1);
+    assert_eq!(
+        (padding[0].section, padding[0].bytes),
+        (".text", 0x1c + 0x3)
+    );
+
+    let gaps = reports::largest_gaps(&map, 1);
+    assert_eq!(gaps.len(), 1);
+    assert_eq!((gaps[0].address, gaps[0].size), (0x80000a4, 0x1c));
+    let after = gaps[0].after.unwrap();
+    assert_eq!((&*after.section, &*after.file), (".rodata", "main.o"));
+}
+
+#[test]
+fn rules() {
+    let map = MapFile::parse(include_str!("fixtures/firmware.bfd.map")).unwrap();
+    let text = map
+        .sections
+        .iter()
+        .find(|g| g.section.name == ".text")
+        .unwrap();
+    let placed: Vec<(&str, Option<&str>)> = text
+        .file_section_groups
+        .iter()
+        .map(|fsg| (&*fsg.file_section.section, fsg.rule.as_deref()))
+        .collect();
+    assert_eq!(placed[0], (".text", Some("*(.text .text.*)")));
+    assert_eq!(placed[3], (".rodata", Some("*(.rodata .rodata.*)")));
+
+    let usage = reports::rule_usage(&map);
+    assert_eq!(usage.len(), 6);
+    assert_eq!((usage[1].inputs, usage[1].bytes), (3, 0x63 + 0x16 + 0x4));
+
+    // the /DISCARD/ rules are not rules of .bss
+    let unmatched: Vec<(&str, &str)> = reports::unmatched_rules(&map)
+        .iter()
+        .map(|u| (u.section, u.rule))
+        .collect();
+    assert_eq!(unmatched, [(".data", "*(.ramfunc*)")]);
+
+    let map = MapFile::parse(include_str!("fixtures/hello.bfd.map")).unwrap();
+    let hash = map.empty_sections.iter().find(|s| s.name == ".hash");
+    assert_eq!(hash.unwrap().rules, ["*(.hash)"]);
+}
+
+#[test]
+fn size_tree() {
+    let map = MapFile::parse(include_str!("fixtures/firmware.bfd.map")).unwrap();
+    let tree = reports::size_tree(&map);
+    assert_eq!(tree.name, "firmware.elf");
+    let regions: Vec<(&str, u64)> = tree.children.iter().map(|r| (r.name, r.size)).collect();
+    assert_eq!(regions, [("FLASH", 200), ("RAM", 84)]);
+    for node in &tree.children[0].children {
+        let children: u64 = node.children.iter().map(|c| c.size).sum();
+        assert_eq!(children, node.size, "{}", node.name);
+    }
+
+    let text = &tree.children[0].children[1];
+    assert_eq!(text.name, ".text");
+    let main = text.children.iter().find(|f| f.name == "main.o").unwrap();
+    let symbols: Vec<(&str, u64)> = main.children.iter().map(|s| (s.name, s.size)).collect();
+    assert_eq!(symbols, [("main", 0x16), ("version", 0x7)]);
+    assert_eq!(text.children.last().unwrap().name, reports::OTHER);
+}
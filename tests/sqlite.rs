@@ -0,0 +1,84 @@
+use std::fs;
+
+use mapper::exporters::Sqlite;
+use mapper::{Exporter, MapFile};
+use rusqlite::Connection;
+
+#[test]
+fn sqlite() {
+    let map = MapFile::parse(include_str!("fixtures/firmware.bfd.map")).unwrap();
+    let path = std::env::temp_dir().join(format!("mapper-{}.sqlite", std::process::id()));
+    // an existing database is replaced
+    Sqlite.export(&map, &path).unwrap();
+    Sqlite.export(&map, &path).unwrap();
+    let connection = Connection::open(&path).unwrap();
+
+    let count = |table: &str| -> i64 {
+        connection
+            .query_row(&format!("SELECT count(*) FROM {table}"), [], |row| {
+                row.get(0)
+            })
+            .unwrap()
+    };
+    assert_eq!(count("sections"), map.sections.len() as i64);
+    assert_eq!(count("memory_regions"), 3);
+    assert_eq!(count("archives"), 1);
+    assert_eq!(count("symbols"), 16);
+
+    let (file, archive, section): (String, String, String) = connection
+        .query_row(
+            "SELECT files.path, archives.path, sections.name FROM symbols
+             JOIN file_sections ON file_sections.id = symbols.file_section_id
+             JOIN files ON files.id = file_sections.file_id
+             JOIN archives ON archives.id = files.archive_id
+             JOIN sections ON sections.id = file_sections.section_id
+             WHERE symbols.name = 'checksum'",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .unwrap();
+    assert_eq!(
+        (file.as_str(), archive.as_str(), section.as_str()),
+        ("libutil.a(util.o)", "libutil.a", ".bss")
+    );
+
+    let (address, kind): (i64, String) = connection
+        .query_row(
+            "SELECT address, kind FROM symbols WHERE name = '_stack_top'",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .unwrap();
+    assert_eq!((address, kind.as_str()), (0x20002000, "linker_script"));
+
+    // bfd maps do not name the region of a section, it is found by address
+    let regions: Vec<(String, Option<String>)> = connection
+        .prepare(
+            "SELECT sections.name, memory_regions.name FROM sections
+             LEFT JOIN memory_regions ON memory_regions.id = sections.memory_region_id
+             ORDER BY sections.id",
+        )
+        .unwrap()
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    let region = |section: &str| {
+        regions
+            .iter()
+            .find(|(name, _)| name == section)
+            .and_then(|(_, region)| region.as_deref())
+    };
+    assert_eq!(region(".text"), Some("FLASH"));
+    assert_eq!(region(".bss"), Some("RAM"));
+
+    let lma: i64 = connection
+        .query_row("SELECT lma FROM symbols WHERE name = 'ticks'", [], |row| {
+            row.get(0)
+        })
+        .unwrap();
+    assert_eq!(lma, 0x80000d0);
+
+    drop(connection);
+    fs::remove_file(&path).unwrap();
+}
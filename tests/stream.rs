@@ -0,0 +1,111 @@
+use std::io::Cursor;
+
+use mapper::exporters::Csv;
+use mapper::formats::SectionGroups;
+use mapper::MapFile;
+
+fn stream(input: &str) -> SectionGroups<Cursor<&[u8]>> {
+    SectionGroups::new(Cursor::new(input.as_bytes()))
+}
+
+#[test]
+fn same_as_parse() {
+    for input in [
+        include_str!("fixtures/firmware.bfd.map"),
+        include_str!("fixtures/hello.bfd.map"),
+        include_str!("fixtures/hello.gold.map"),
+    ] {
+        let map = MapFile::parse(input).unwrap();
+        let mut groups = stream(input);
+        let sections: Vec<_> = groups.by_ref().map(Result::unwrap).collect();
+        assert_eq!(sections, map.sections);
+        assert_eq!(groups.assignments(), map.assignments);
+        assert_eq!(groups.warnings(), map.warnings);
+    }
+}
+
+#[test]
+fn without_header() {
+    for (input, header) in [
+        (include_str!("fixtures/hello.gold.map"), "Memory map\n"),
+        (
+            include_str!("fixtures/firmware.bfd.map"),
+            "Linker script and memory map\n",
+        ),
+    ] {
+        let input = input.replacen(header, "", 1);
+        let map = MapFile::parse(&input).unwrap();
+        assert!(!map.sections.is_empty());
+        let mut groups = stream(&input);
+        let sections: Vec<_> = groups.by_ref().map(Result::unwrap).collect();
+        assert_eq!(sections, map.sections);
+        assert!(groups.found_memory_map());
+        assert_eq!(groups.warnings(), map.warnings);
+    }
+}
+
+#[test]
+fn unrecognised_line() {
+    let input = include_str!("fixtures/firmware.bfd.map").replacen("\n.data", "\n???\n.data", 1);
+    let map = MapFile::parse(&input).unwrap();
+    assert_eq!(map.warnings.len(), 1);
+
+    let mut groups = stream(&input);
+    let sections: Vec<_> = groups.by_ref().map(Result::unwrap).collect();
+    assert_eq!(sections, map.sections);
+    assert_eq!(groups.warnings(), map.warnings);
+}
+
+#[test]
+fn csv() {
+    let input = include_str!("fixtures/firmware.bfd.map");
+    let mut csv = Vec::new();
+    Csv.write_stream(&mut stream(input), &mut csv).unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+    let mut rows: Vec<&str> = csv.lines().collect();
+    assert_eq!(
+        rows.remove(0),
+        "symbol,address,file,old_section,new_section,lma,kind,rule"
+    );
+    assert_eq!(
+        rows[0],
+        "vectors,0x0000000008000000,startup.o,.isr_vector,.isr_vector,,object,*(.isr_vector)"
+    );
+    assert_eq!(
+        rows.last().unwrap(),
+        &"_stack_top,0x0000000020002000,,,,,linker_script,"
+    );
+
+    // the same rows as the whole-file export, in map order
+    let mut whole = Vec::new();
+    mapper::Exporter::write(&Csv, &MapFile::parse(input).unwrap(), &mut whole).unwrap();
+    let whole = String::from_utf8(whole).unwrap();
+    let mut whole: Vec<&str> = whole.lines().skip(1).collect();
+    rows.sort_unstable();
+    whole.sort_unstable();
+    assert_eq!(rows, whole);
+}
+
+#[test]
+fn latin1() {
+    let input = include_str!("fixtures/firmware.bfd.map").replace("main.o", "m\u{e4}in.o");
+    let latin1: Vec<u8> = input.chars().map(|c| c as u8).collect();
+    let groups = SectionGroups::new(Cursor::new(latin1));
+    let files: Vec<String> = groups
+        .flat_map(|group| group.unwrap().file_section_groups)
+        .map(|fsg| fsg.file_section.file.into_owned())
+        .collect();
+    assert!(files.contains(&"m\u{fffd}in.o".to_string()));
+}
+
+#[test]
+fn no_memory_map() {
+    let mut groups = stream(include_str!("fixtures/hello.ld64.map"));
+    assert_eq!(groups.by_ref().count(), 0);
+    assert!(!groups.found_memory_map());
+    assert_eq!(groups.warnings()[0].message, "no memory map found");
+
+    let mut groups = stream(include_str!("fixtures/hello.bfd.map"));
+    assert!(groups.by_ref().count() > 0);
+    assert!(groups.found_memory_map());
+}
@@ -0,0 +1,47 @@
+use std::io::{Cursor, Read};
+
+use mapper::exporters::Xlsx;
+use mapper::{Exporter, MapFile};
+use zip::ZipArchive;
+
+/// The XML part `name` of the workbook.
+fn part(workbook: &mut ZipArchive<Cursor<Vec<u8>>>, name: &str) -> String {
+    let mut xml = String::new();
+    workbook
+        .by_name(name)
+        .unwrap()
+        .read_to_string(&mut xml)
+        .unwrap();
+    xml
+}
+
+#[test]
+fn xlsx() {
+    let map = MapFile::parse(include_str!("fixtures/firmware.bfd.map")).unwrap();
+    let mut bytes = Vec::new();
+    Xlsx.write(&map, &mut bytes).unwrap();
+    let mut workbook = ZipArchive::new(Cursor::new(bytes)).unwrap();
+
+    let sheets = part(&mut workbook, "xl/workbook.xml");
+    for name in [
+        "symbols",
+        "file_sections",
+        "sections",
+        "memory_regions",
+        "inputs",
+        "padding",
+    ] {
+        assert!(
+            sheets.contains(&format!(r#"<sheet name="{name}""#)),
+            "{name}"
+        );
+    }
+
+    // memory_regions: FLASH starts at 0x08000000 and has 212 bytes used
+    let regions = part(&mut workbook, "xl/worksheets/sheet4.xml");
+    assert!(regions
+        .contains(r#"<pane ySplit="1" topLeftCell="A2" activePane="bottomLeft" state="frozen"/>"#));
+    assert!(regions.contains(r#"<autoFilter ref="A1:F4"/>"#));
+    assert!(regions.contains(r#"<c r="B2"><v>134217728</v></c>"#));
+    assert!(regions.contains(r#"<c r="D2"><v>212</v></c>"#));
+}